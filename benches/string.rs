@@ -17,22 +17,26 @@ fn bench_unicode_to_utf8(c: &mut Criterion) {
     });
 }
 
+#[cfg(windows)]
 fn bench_unicode_to_ansi(c: &mut Criterion) {
     let s = WString::try_from("Hello World").unwrap();
     c.bench_function("Unicode to ANSI", |b| b.iter(|| s.to_astring().unwrap()));
 }
 
+#[cfg(windows)]
 fn bench_ansi_to_unicode(c: &mut Criterion) {
     let s = AString::try_from("Hello World").unwrap();
     c.bench_function("ANSI to Unicode", |b| b.iter(|| s.to_wstring().unwrap()));
 }
 
+#[cfg(windows)]
 fn bench_utf8_to_ansi(c: &mut Criterion) {
     c.bench_function("UTF-8 to ANSI", |b| {
         b.iter(|| AString::try_from("Hello World").unwrap())
     });
 }
 
+#[cfg(windows)]
 fn bench_ansi_to_utf8(c: &mut Criterion) {
     let s = AString::try_from("Hello World").unwrap();
     c.bench_function("ANSI to UTF-8", |b| {
@@ -53,6 +57,7 @@ fn bench_unicode_to_utf8_lossy(c: &mut Criterion) {
     });
 }
 
+#[cfg(windows)]
 fn bench_unicode_to_ansi_lossy(c: &mut Criterion) {
     let s = WString::try_from("Hello World").unwrap();
     c.bench_function("Unicode to ANSI lossy", |b| {
@@ -60,6 +65,7 @@ fn bench_unicode_to_ansi_lossy(c: &mut Criterion) {
     });
 }
 
+#[cfg(windows)]
 fn bench_ansi_to_unicode_lossy(c: &mut Criterion) {
     let s = AString::try_from("Hello World").unwrap();
     c.bench_function("ANSI to Unicode lossy", |b| {
@@ -67,12 +73,14 @@ fn bench_ansi_to_unicode_lossy(c: &mut Criterion) {
     });
 }
 
+#[cfg(windows)]
 fn bench_utf8_to_ansi_lossy(c: &mut Criterion) {
     c.bench_function("UTF-8 to ANSI lossy", |b| {
         b.iter(|| AString::from_str_lossy("Hello World!🍣食べたい"))
     });
 }
 
+#[cfg(windows)]
 fn bench_ansi_to_utf8_lossy(c: &mut Criterion) {
     let s = AString::try_from("Hello World").unwrap();
     c.bench_function("ANSI to UTF-8 lossy", |b| b.iter(|| s.to_string_lossy()));
@@ -82,16 +90,24 @@ criterion_group!(
     conversion_benches,
     bench_utf8_to_unicode,
     bench_unicode_to_utf8,
+    bench_utf8_to_unicode_lossy,
+    bench_unicode_to_utf8_lossy,
+);
+
+#[cfg(windows)]
+criterion_group!(
+    ansi_conversion_benches,
     bench_unicode_to_ansi,
     bench_ansi_to_unicode,
     bench_utf8_to_ansi,
     bench_ansi_to_utf8,
-    bench_utf8_to_unicode_lossy,
-    bench_unicode_to_utf8_lossy,
     bench_unicode_to_ansi_lossy,
     bench_ansi_to_unicode_lossy,
     bench_utf8_to_ansi_lossy,
     bench_ansi_to_utf8_lossy,
 );
 
+#[cfg(windows)]
+criterion_main!(conversion_benches, ansi_conversion_benches);
+#[cfg(not(windows))]
 criterion_main!(conversion_benches);
@@ -1,12 +1,12 @@
 // Copyright takubokudori.
 // This source code is licensed under the MIT or Apache-2.0 license.
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 use core::ffi::c_void;
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 use windy::*;
 
 #[allow(non_snake_case)]
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 #[link(name = "user32")]
 unsafe extern "system" {
     pub fn MessageBoxA(
@@ -24,7 +24,7 @@ unsafe extern "system" {
     ) -> i32;
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 fn message_box_a() {
     let text = AString::from_str("Hello").unwrap();
     let caption = AString::from_str("CaptionA").unwrap();
@@ -33,7 +33,7 @@ fn message_box_a() {
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 fn message_box_w() {
     let text = WString::from_str("World").unwrap();
     let caption = WString::from_str("CaptionW").unwrap();
@@ -42,7 +42,7 @@ fn message_box_w() {
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(windows, feature = "std"))]
 fn main() {
     println!("*****message_box_a*****");
     message_box_a();
@@ -50,7 +50,7 @@ fn main() {
     message_box_w();
 }
 
-#[cfg(not(feature = "std"))]
+#[cfg(not(all(windows, feature = "std")))]
 fn main() {
-    panic!("Use std feature!");
+    panic!("Use std feature on Windows!");
 }
@@ -0,0 +1,331 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Command line parsing and quoting.
+//!
+//! A Windows program receives its command line as a single string, and
+//! splits it into arguments by itself. [`WStr::parse_args`] splits a command
+//! line like `CommandLineToArgvW`, and [`WStr::parse_args_with`] splits it
+//! like the MSVC CRT. [`WString::join_args`] builds a command line that is
+//! split back into the same arguments.
+use crate::*;
+
+/// Rules to split a command line into arguments.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ArgsStyle {
+    /// `CommandLineToArgvW` of shell32.dll.
+    ///
+    /// The program name ends at the closing quote if it starts with `"`,
+    /// otherwise at the first space or tab. `""` in a quoted block is a
+    /// literal `"` and ends the block.
+    Shell32,
+    /// The MSVC CRT prior to Visual C++ 2008, including msvcrt.dll.
+    ///
+    /// This is the same as [`ArgsStyle::Shell32`] except for an empty
+    /// command line.
+    CrtPre2008,
+    /// The MSVC CRT since Visual C++ 2008, including the UCRT.
+    ///
+    /// The program name ends at the first space or tab outside quotes, and
+    /// quotes in it are removed. `""` in a quoted block is a literal `"`,
+    /// and the block continues.
+    Crt,
+}
+
+/// An error indicating that arguments could not be joined into a command
+/// line.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum JoinArgsError {
+    /// The program name contains `"`, which cannot be escaped.
+    InvalidProgramName,
+    /// The argument at `index` contains a null character.
+    InteriorNul { index: usize },
+    /// The command line has `len` characters, which is not shorter than
+    /// [`UNICODE_STRING_MAX_CHARS`].
+    TooLong { len: usize },
+}
+
+impl fmt::Display for JoinArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidProgramName => {
+                write!(f, "program name contains a double quote")
+            }
+            Self::InteriorNul { index } => {
+                write!(f, "nul byte found in the argument at index: {}", index)
+            }
+            Self::TooLong { len } => {
+                write!(f, "command line is too long: {} characters", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JoinArgsError {}
+
+#[inline]
+fn is_blank(x: u16) -> bool { x == b' ' as u16 || x == b'\t' as u16 }
+
+const QUOTE: u16 = b'"' as u16;
+const BACKSLASH: u16 = b'\\' as u16;
+
+/// Parses the program name and returns it and the rest of `s`.
+fn parse_program(s: &[u16], style: ArgsStyle) -> (Vec<u16>, &[u16]) {
+    if style != ArgsStyle::Crt {
+        if s.first() == Some(&QUOTE) {
+            return match s[1..].iter().position(|&x| x == QUOTE) {
+                Some(i) => (s[1..i + 1].to_vec(), &s[i + 2..]),
+                None => (s[1..].to_vec(), &[]),
+            };
+        }
+        let i = s.iter().position(|&x| is_blank(x)).unwrap_or(s.len());
+        return (s[..i].to_vec(), &s[i..]);
+    }
+    let mut v = Vec::new();
+    let mut in_quotes = false;
+    let mut i = 0;
+    while let Some(&x) = s.get(i) {
+        if x == QUOTE {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && is_blank(x) {
+            break;
+        } else {
+            v.push(x);
+        }
+        i += 1;
+    }
+    (v, &s[i..])
+}
+
+/// Appends the program name `s` to `v`.
+fn push_program(v: &mut Vec<u16>, s: &[u16]) -> Result<(), JoinArgsError> {
+    if s.contains(&QUOTE) {
+        return Err(JoinArgsError::InvalidProgramName);
+    }
+    if s.is_empty() || s.iter().any(|&x| is_blank(x)) {
+        v.push(QUOTE);
+        v.extend_from_slice(s);
+        v.push(QUOTE);
+    } else {
+        v.extend_from_slice(s);
+    }
+    Ok(())
+}
+
+/// Appends the argument `s` to `v` with quotes if needed.
+pub(crate) fn push_arg(v: &mut Vec<u16>, s: &[u16]) {
+    const SPECIAL: [u16; 5] = [0x20, 0x09, 0x0A, 0x0B, QUOTE];
+    if !s.is_empty() && !s.iter().any(|x| SPECIAL.contains(x)) {
+        v.extend_from_slice(s);
+        return;
+    }
+    v.push(QUOTE);
+    let mut n = 0;
+    for &x in s {
+        if x == BACKSLASH {
+            n += 1;
+            continue;
+        }
+        // Backslashes followed by `"` are escaped.
+        let n2 = if x == QUOTE { 2 * n + 1 } else { n };
+        v.resize(v.len() + n2, BACKSLASH);
+        v.push(x);
+        n = 0;
+    }
+    // Backslashes followed by the closing quote are escaped.
+    v.resize(v.len() + 2 * n, BACKSLASH);
+    v.push(QUOTE);
+}
+
+/// Parses an argument and returns it and the rest of `s`.
+fn parse_arg(mut s: &[u16], style: ArgsStyle) -> (Vec<u16>, &[u16]) {
+    let mut v = Vec::new();
+    let mut in_quotes = false;
+    loop {
+        let n = s.iter().position(|&x| x != BACKSLASH).unwrap_or(s.len());
+        s = &s[n..];
+        let Some(&x) = s.first() else {
+            v.resize(v.len() + n, BACKSLASH);
+            break;
+        };
+        if x != QUOTE {
+            v.resize(v.len() + n, BACKSLASH);
+            if !in_quotes && is_blank(x) {
+                break;
+            }
+            v.push(x);
+            s = &s[1..];
+            continue;
+        }
+        v.resize(v.len() + n / 2, BACKSLASH);
+        if n % 2 == 1 {
+            // An escaped quote.
+            v.push(QUOTE);
+        } else if in_quotes && s.get(1) == Some(&QUOTE) {
+            v.push(QUOTE);
+            s = &s[1..];
+            if style != ArgsStyle::Crt {
+                in_quotes = false;
+            }
+        } else {
+            in_quotes = !in_quotes;
+        }
+        s = &s[1..];
+    }
+    (v, s)
+}
+
+impl WStr {
+    /// Splits a command line into arguments like `CommandLineToArgvW`.
+    ///
+    /// See [`WStr::parse_args_with`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    ///
+    /// let args = wstr!(r#""C:\a b\x.exe" "c d" e\"f"#).parse_args();
+    /// let args: Vec<String> = args.iter().map(|x| x.to_string_lossy()).collect();
+    /// assert_eq!(args, [r"C:\a b\x.exe", "c d", r#"e"f"#]);
+    /// ```
+    #[inline]
+    pub fn parse_args(&self) -> Vec<WString> {
+        self.parse_args_with(ArgsStyle::Shell32)
+    }
+
+    /// Splits a command line into arguments with `style`.
+    ///
+    /// The first argument is the program name, which does not handle
+    /// backslashes. The other arguments are separated by spaces and tabs
+    /// outside quoted blocks, and handle backslashes as follows:
+    ///
+    /// - `2n` backslashes followed by `"` are `n` backslashes, and the `"`
+    ///   starts or ends a quoted block.
+    /// - `2n + 1` backslashes followed by `"` are `n` backslashes and a
+    ///   literal `"`.
+    /// - Other backslashes are literal.
+    ///
+    /// If `self` is empty, [`ArgsStyle::Shell32`] returns an empty [`Vec`],
+    /// while `CommandLineToArgvW` returns the path of the current
+    /// executable. The other styles return an empty program name.
+    pub fn parse_args_with(&self, style: ArgsStyle) -> Vec<WString> {
+        let s = self.to_bytes();
+        if s.is_empty() && style == ArgsStyle::Shell32 {
+            return Vec::new();
+        }
+        let (program, mut s) = parse_program(s, style);
+        let mut ret = vec![unsafe { WString::new_unchecked(program) }];
+        loop {
+            let n = s.iter().position(|&x| !is_blank(x)).unwrap_or(s.len());
+            s = &s[n..];
+            if s.is_empty() {
+                break;
+            }
+            let (arg, rest) = parse_arg(s, style);
+            ret.push(unsafe { WString::new_unchecked(arg) });
+            s = rest;
+        }
+        ret
+    }
+
+    /// Quotes `self` as an argument of a command line.
+    ///
+    /// `self` is left intact if it does not need quotes. Otherwise, `self`
+    /// is enclosed in quotes, and `"`s and backslashes followed by `"` are
+    /// escaped with backslashes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    ///
+    /// let s = wstr!(r#"a "b" c\"#).quote_arg();
+    /// assert_eq!(s.to_string_lossy(), r#""a \"b\" c\\""#);
+    /// ```
+    pub fn quote_arg(&self) -> WString {
+        let mut v = Vec::new();
+        push_arg(&mut v, self.to_bytes());
+        unsafe { WString::new_unchecked(v) }
+    }
+}
+
+impl WString {
+    /// Joins a program name and arguments into a command line.
+    ///
+    /// The command line is split back into the same arguments by
+    /// [`WStr::parse_args_with`] with any [`ArgsStyle`]. The program name is
+    /// enclosed in quotes if it is empty or contains spaces or tabs, and the
+    /// arguments are quoted by [`WStr::quote_arg`].
+    ///
+    /// If the program name contains `"`, this function returns
+    /// [`JoinArgsError::InvalidProgramName`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WString, wstr};
+    ///
+    /// let args = [wstr!(r"C:\a b\x.exe"), wstr!("c d"), wstr!(r#"e"f"#)];
+    /// let s = WString::join_args(args).unwrap();
+    /// assert_eq!(s.to_string_lossy(), r#""C:\a b\x.exe" "c d" "e\"f""#);
+    /// assert!(s.parse_args().iter().map(|x| &**x).eq(args));
+    /// ```
+    pub fn join_args<I, S>(args: I) -> Result<Self, JoinArgsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<WStr>,
+    {
+        let mut v = Vec::new();
+        for (i, s) in args.into_iter().enumerate() {
+            let s = s.as_ref().to_bytes();
+            if i == 0 {
+                push_program(&mut v, s)?;
+            } else {
+                v.push(b' ' as u16);
+                push_arg(&mut v, s);
+            }
+        }
+        Ok(unsafe { Self::new_unchecked(v) })
+    }
+
+    /// Joins a program name and UTF-8 arguments into a command line.
+    ///
+    /// If an argument contains a null character, this function returns
+    /// [`JoinArgsError::InteriorNul`].
+    /// See [`WString::join_args`] for the details.
+    pub fn join_str_args<I, S>(args: I) -> Result<Self, JoinArgsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args = args
+            .into_iter()
+            .enumerate()
+            .map(|(index, s)| {
+                WString::new(s.as_ref().encode_utf16().collect::<Vec<u16>>())
+                    .map_err(|_| JoinArgsError::InteriorNul { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::join_args(args)
+    }
+
+    /// Joins a program name and arguments into a command line, and checks
+    /// that `CreateProcessW` accepts its length.
+    ///
+    /// If the command line is not shorter than
+    /// [`UNICODE_STRING_MAX_CHARS`], this function returns
+    /// [`JoinArgsError::TooLong`].
+    /// See [`WString::join_args`] for the details.
+    pub fn join_args_checked<I, S>(args: I) -> Result<Self, JoinArgsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<WStr>,
+    {
+        let s = Self::join_args(args)?;
+        let len = s.to_bytes().len();
+        if len >= UNICODE_STRING_MAX_CHARS {
+            return Err(JoinArgsError::TooLong { len });
+        }
+        Ok(s)
+    }
+}
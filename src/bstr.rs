@@ -0,0 +1,485 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! BSTR strings.
+//!
+//! A BSTR is a Unicode string preceded by a 4-byte length prefix in bytes,
+//! and followed by a null terminator. A pointer to a BSTR points to the
+//! first character, not to the prefix. A BSTR can contain null characters,
+//! and a null pointer is treated as an empty string.
+#[cfg(feature = "std")]
+use crate::__lib::{fmt::Write, ops};
+use crate::{
+    __lib::{hash, mem, ptr, slice},
+    *,
+};
+
+/// Allocates and frees BSTRs.
+pub trait BStrAllocator {
+    /// Allocates a BSTR that contains a copy of `s`.
+    ///
+    /// Returns a pointer to the first character, or a null pointer if the
+    /// allocation fails.
+    fn allocate(&self, s: &[u16]) -> *mut u16;
+
+    /// Frees `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null BSTR allocated by this allocator, and must
+    /// not be freed twice.
+    unsafe fn free(&self, ptr: *mut u16);
+}
+
+/// Allocator that calls `SysAllocStringLen` and `SysFreeString`.
+///
+/// A BSTR passed to COM must be allocated by this allocator.
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SysBStrAllocator;
+
+#[cfg(windows)]
+impl BStrAllocator for SysBStrAllocator {
+    fn allocate(&self, s: &[u16]) -> *mut u16 {
+        unsafe { SysAllocStringLen(s.as_ptr(), s.len() as UINT) }
+    }
+
+    #[inline]
+    unsafe fn free(&self, ptr: *mut u16) { unsafe { SysFreeString(ptr) } }
+}
+
+/// Allocator that uses the Rust global allocator.
+///
+/// The memory layout is the same as `SysAllocStringLen`, so BSTRs can be
+/// handled on non-Windows hosts.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RustBStrAllocator;
+
+#[cfg(feature = "std")]
+impl RustBStrAllocator {
+    /// Returns the layout of a BSTR of `byte_len` bytes.
+    fn layout(byte_len: usize) -> std::alloc::Layout {
+        // The prefix, the string and the null terminator.
+        std::alloc::Layout::from_size_align(4 + byte_len + 2, 4).unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl BStrAllocator for RustBStrAllocator {
+    fn allocate(&self, s: &[u16]) -> *mut u16 {
+        let byte_len = s.len() * 2;
+        unsafe {
+            let p = std::alloc::alloc(Self::layout(byte_len));
+            if p.is_null() {
+                return ptr::null_mut();
+            }
+            (p as *mut u32).write(byte_len as u32);
+            let data = p.add(4) as *mut u16;
+            ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+            data.add(s.len()).write(0);
+            data
+        }
+    }
+
+    unsafe fn free(&self, ptr: *mut u16) {
+        unsafe {
+            let p = (ptr as *mut u8).sub(4);
+            let byte_len = (p as *const u32).read() as usize;
+            std::alloc::dealloc(p, Self::layout(byte_len));
+        }
+    }
+}
+
+/// The default allocator of [`BString`].
+///
+/// [`SysBStrAllocator`] on Windows, and [`RustBStrAllocator`] on the other
+/// hosts.
+#[cfg(windows)]
+pub type DefaultBStrAllocator = SysBStrAllocator;
+
+/// The default allocator of [`BString`].
+///
+/// [`SysBStrAllocator`] on Windows, and [`RustBStrAllocator`] on the other
+/// hosts.
+#[cfg(all(not(windows), feature = "std"))]
+pub type DefaultBStrAllocator = RustBStrAllocator;
+
+/// An empty BSTR that is used for a null pointer.
+#[repr(C)]
+struct EmptyBStr {
+    len: u32,
+    nul: u16,
+}
+
+static EMPTY: EmptyBStr = EmptyBStr { len: 0, nul: 0 };
+
+/// The size of the length prefix in bytes.
+const PREFIX_LEN: usize = 4;
+
+/// Represents a borrowed BSTR.
+///
+/// [`BStr`] is always a part of a BSTR, so [`BStr::as_ptr`] can be passed to
+/// functions that take a BSTR.
+///
+/// A BSTR allocated by `SysAllocStringByteLen` may have an odd length in
+/// bytes. The last byte of such a BSTR is only available through
+/// [`BStr::as_bytes`].
+#[repr(transparent)]
+pub struct BStr {
+    /// The length prefix, the string and the null terminator.
+    inner: [u8],
+}
+
+impl BStr {
+    /// Creates &[`BStr`] from `ptr`.
+    ///
+    /// A null pointer is treated as an empty string.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or a valid BSTR during `'a`.
+    pub unsafe fn from_raw<'a>(ptr: *const u16) -> &'a Self {
+        unsafe {
+            let p = if ptr.is_null() {
+                ptr::addr_of!(EMPTY) as *const u8
+            } else {
+                (ptr as *const u8).sub(PREFIX_LEN)
+            };
+            let byte_len = (p as *const u32).read_unaligned() as usize;
+            mem::transmute::<&[u8], &Self>(slice::from_raw_parts(
+                p,
+                PREFIX_LEN + byte_len + 2,
+            ))
+        }
+    }
+
+    /// Returns a pointer to the BSTR.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u16 {
+        self.inner[PREFIX_LEN..].as_ptr() as *const u16
+    }
+
+    /// Returns the length in code units.
+    ///
+    /// If [`BStr::byte_len`] is odd, the last byte is not counted.
+    #[inline]
+    pub fn len(&self) -> usize { self.byte_len() as usize / 2 }
+
+    /// Returns `true` if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.byte_len() == 0 }
+
+    /// Returns the length prefix in bytes.
+    ///
+    /// It is odd if the BSTR is allocated by `SysAllocStringByteLen` with an
+    /// odd length.
+    #[inline]
+    pub fn byte_len(&self) -> u32 {
+        let mut prefix = [0; PREFIX_LEN];
+        prefix.copy_from_slice(&self.inner[..PREFIX_LEN]);
+        u32::from_ne_bytes(prefix)
+    }
+
+    /// Returns the bytes of the string without the null terminator.
+    ///
+    /// Unlike [`BStr::as_slice`], the slice contains the last byte of a BSTR
+    /// whose length is odd.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner[PREFIX_LEN..self.inner.len() - 2]
+    }
+
+    /// Returns the code units of the string without the null terminator.
+    ///
+    /// The slice may contain null characters. If [`BStr::byte_len`] is odd,
+    /// the last byte is not contained.
+    #[inline]
+    pub fn as_slice(&self) -> &[u16] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    /// Returns the code units of the string with the null terminator.
+    ///
+    /// Returns `None` if [`BStr::byte_len`] is odd, since the null
+    /// terminator is not aligned to a code unit.
+    #[inline]
+    pub fn as_slice_with_nul(&self) -> Option<&[u16]> {
+        if !self.byte_len().is_multiple_of(2) {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(self.as_ptr(), self.len() + 1) })
+    }
+
+    /// Returns &[`WStr`] without copying.
+    ///
+    /// If the string contains a null character or [`BStr::byte_len`] is odd,
+    /// this function returns [`FromBytesWithNulError`].
+    #[inline]
+    pub fn as_wstr(&self) -> Result<&WStr, FromBytesWithNulError> {
+        match self.as_slice_with_nul() {
+            Some(x) => WStr::from_bytes_with_nul(x),
+            None => Err(FromBytesWithNulError::NotNulTerminated {
+                position: self.len(),
+            }),
+        }
+    }
+
+    /// Converts [`BStr`] to [`WString`].
+    ///
+    /// If the string contains a null character, this function returns
+    /// [`NulError`].
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_wstring(&self) -> Result<WString, NulError<u16>> {
+        WString::new(self.as_slice())
+    }
+
+    /// Converts [`BStr`] to UTF-8 string.
+    ///
+    /// Null characters are kept, and unpaired surrogates are replaced with
+    /// `\u{FFFD}`.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_slice())
+    }
+}
+
+impl PartialEq for BStr {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool { self.as_bytes() == other.as_bytes() }
+}
+
+impl Eq for BStr {}
+
+impl PartialEq<WStr> for BStr {
+    #[inline]
+    fn eq(&self, other: &WStr) -> bool {
+        self.as_slice_with_nul() == Some(other.to_bytes_with_nul())
+    }
+}
+
+impl hash::Hash for BStr {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl fmt::Debug for BStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "std")]
+        {
+            f.write_char('"')?;
+            fmt::Display::fmt(&self.to_string_lossy().escape_debug(), f)?;
+            f.write_char('"')
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            fmt::Debug::fmt(self.as_slice(), f)
+        }
+    }
+}
+
+impl<'a> Default for &'a BStr {
+    #[inline]
+    fn default() -> &'a BStr { unsafe { BStr::from_raw(ptr::null()) } }
+}
+
+/// Represents an owned BSTR.
+///
+/// The string is freed by `A` when it is dropped. An empty [`BString`] may
+/// hold a null pointer.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::BString;
+///
+/// let s = BString::from_str("te\0st");
+/// assert_eq!(s.len(), 5);
+/// assert_eq!(s.byte_len(), 10);
+/// // Passes the ownership to a COM out-parameter.
+/// let ptr: *mut u16 = s.into_raw();
+/// let s = unsafe { BString::from_raw(ptr) };
+/// assert_eq!(s.to_string_lossy(), "te\0st");
+/// ```
+#[cfg(feature = "std")]
+pub struct BString<A: BStrAllocator = DefaultBStrAllocator> {
+    ptr: *mut u16,
+    alloc: A,
+}
+
+#[cfg(feature = "std")]
+impl BString {
+    /// Creates [`BString`] from `s` with the default allocator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation fails or `s` is too long.
+    #[inline]
+    pub fn from_wide(s: &[u16]) -> Self {
+        Self::from_wide_in(s, Default::default())
+    }
+
+    /// Creates [`BString`] from `s` with the default allocator.
+    #[inline]
+    pub fn from_wstr(s: &WStr) -> Self { Self::from_wide(s.to_bytes()) }
+
+    /// Creates [`BString`] from `s` with the default allocator.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        Self::from_wide(&s.encode_utf16().collect::<Vec<u16>>())
+    }
+
+    /// Creates [`BString`] from a BSTR allocated by the default allocator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or a BSTR allocated by the default allocator.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *mut u16) -> Self {
+        unsafe { Self::from_raw_in(ptr, Default::default()) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> BString<A> {
+    /// Creates [`BString`] from `s` with `alloc`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation fails or `s` is too long.
+    pub fn from_wide_in(s: &[u16], alloc: A) -> Self {
+        assert!(s.len() <= u32::MAX as usize / 2, "BSTR is too long");
+        let ptr = alloc.allocate(s);
+        assert!(!ptr.is_null(), "failed to allocate BSTR");
+        Self { ptr, alloc }
+    }
+
+    /// Creates [`BString`] from a BSTR allocated by `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or a BSTR allocated by `alloc`.
+    #[inline]
+    pub unsafe fn from_raw_in(ptr: *mut u16, alloc: A) -> Self {
+        Self { ptr, alloc }
+    }
+
+    /// Consumes [`BString`] and returns the BSTR without freeing it.
+    ///
+    /// The pointer may be null if the string is empty.
+    pub fn into_raw(self) -> *mut u16 {
+        let mut this = mem::ManuallyDrop::new(self);
+        unsafe { ptr::drop_in_place(&mut this.alloc) };
+        this.ptr
+    }
+
+    /// Returns &[`BStr`].
+    #[inline]
+    pub fn as_bstr(&self) -> &BStr { self }
+
+    /// Returns the allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A { &self.alloc }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator + Default> Default for BString<A> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+            alloc: A::default(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> ops::Deref for BString<A> {
+    type Target = BStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target { unsafe { BStr::from_raw(self.ptr) } }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> AsRef<BStr> for BString<A> {
+    #[inline]
+    fn as_ref(&self) -> &BStr { self }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> Drop for BString<A> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { self.alloc.free(self.ptr) }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator + Clone> Clone for BString<A> {
+    fn clone(&self) -> Self {
+        Self::from_wide_in(self.as_slice(), self.alloc.clone())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> PartialEq for BString<A> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool { (**self).eq(&**other) }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> Eq for BString<A> {}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> PartialEq<BStr> for BString<A> {
+    #[inline]
+    fn eq(&self, other: &BStr) -> bool { (**self).eq(other) }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> hash::Hash for BString<A> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) { (**self).hash(state) }
+}
+
+#[cfg(feature = "std")]
+impl<A: BStrAllocator> fmt::Debug for BString<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&WStr> for BString {
+    #[inline]
+    fn from(x: &WStr) -> Self { Self::from_wstr(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&WString> for BString {
+    #[inline]
+    fn from(x: &WString) -> Self { Self::from_wstr(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&str> for BString {
+    #[inline]
+    fn from(x: &str) -> Self { Self::from_str(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&BStr> for BString {
+    #[inline]
+    fn from(x: &BStr) -> Self { Self::from_wide(x.as_slice()) }
+}
+
+// The string is owned by `BString`.
+#[cfg(feature = "std")]
+unsafe impl<A: BStrAllocator + Send> Send for BString<A> {}
+#[cfg(feature = "std")]
+unsafe impl<A: BStrAllocator + Sync> Sync for BString<A> {}
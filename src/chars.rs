@@ -0,0 +1,264 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Character iteration over [`WStr`].
+use crate::*;
+
+/// An error that indicates an unpaired surrogate in a Unicode string.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UnpairedSurrogate {
+    position: usize,
+    unit: u16,
+}
+
+impl UnpairedSurrogate {
+    /// Returns the position of the unpaired surrogate in code units.
+    #[inline]
+    pub fn position(&self) -> usize { self.position }
+
+    /// Returns the unpaired surrogate.
+    #[inline]
+    pub fn unit(&self) -> u16 { self.unit }
+}
+
+impl fmt::Display for UnpairedSurrogate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unpaired surrogate {:#06X} found at position: {}",
+            self.unit, self.position
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnpairedSurrogate {}
+
+#[inline]
+fn is_high_surrogate(x: u16) -> bool { (0xD800..0xDC00).contains(&x) }
+
+#[inline]
+fn is_low_surrogate(x: u16) -> bool { (0xDC00..0xE000).contains(&x) }
+
+#[inline]
+fn combine_surrogates(hi: u16, lo: u16) -> u32 {
+    0x10000 + (((hi as u32) & 0x3FF) << 10 | ((lo as u32) & 0x3FF))
+}
+
+/// Converts a code point to `char`, or returns an error for a surrogate.
+#[inline]
+fn to_char(position: usize, cp: u32) -> Result<char, UnpairedSurrogate> {
+    char::from_u32(cp).ok_or(UnpairedSurrogate {
+        position,
+        unit: cp as u16,
+    })
+}
+
+/// An iterator over code points of [`WStr`] with their positions.
+///
+/// This struct is created by [`WStr::code_point_indices`].
+#[derive(Clone, Debug)]
+pub struct CodePointIndices<'a> {
+    bytes: &'a [u16],
+    front: usize,
+    back: usize,
+}
+
+impl Iterator for CodePointIndices<'_> {
+    type Item = (usize, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let i = self.front;
+        let x = self.bytes[i];
+        if is_high_surrogate(x)
+            && i + 1 < self.back
+            && is_low_surrogate(self.bytes[i + 1])
+        {
+            self.front += 2;
+            return Some((i, combine_surrogates(x, self.bytes[i + 1])));
+        }
+        self.front += 1;
+        Some((i, x as u32))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n.div_ceil(2), Some(n))
+    }
+}
+
+impl DoubleEndedIterator for CodePointIndices<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let i = self.back - 1;
+        let x = self.bytes[i];
+        if is_low_surrogate(x)
+            && i > self.front
+            && is_high_surrogate(self.bytes[i - 1])
+        {
+            self.back -= 2;
+            return Some((i - 1, combine_surrogates(self.bytes[i - 1], x)));
+        }
+        self.back -= 1;
+        Some((i, x as u32))
+    }
+}
+
+/// An iterator over code points of [`WStr`].
+///
+/// This struct is created by [`WStr::code_points`].
+#[derive(Clone, Debug)]
+pub struct CodePoints<'a>(CodePointIndices<'a>);
+
+impl Iterator for CodePoints<'_> {
+    type Item = u32;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> { self.0.next().map(|x| x.1) }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl DoubleEndedIterator for CodePoints<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|x| x.1)
+    }
+}
+
+/// An iterator over characters of [`WStr`] with their positions.
+///
+/// This struct is created by [`WStr::char_indices`].
+#[derive(Clone, Debug)]
+pub struct CharIndices<'a>(CodePointIndices<'a>);
+
+impl Iterator for CharIndices<'_> {
+    type Item = (usize, Result<char, UnpairedSurrogate>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(i, x)| (i, to_char(i, x)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl DoubleEndedIterator for CharIndices<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(i, x)| (i, to_char(i, x)))
+    }
+}
+
+/// An iterator over characters of [`WStr`].
+///
+/// This struct is created by [`WStr::chars`].
+#[derive(Clone, Debug)]
+pub struct Chars<'a>(CodePointIndices<'a>);
+
+impl Iterator for Chars<'_> {
+    type Item = Result<char, UnpairedSurrogate>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(i, x)| to_char(i, x))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(i, x)| to_char(i, x))
+    }
+}
+
+/// An iterator over characters of [`WStr`] that replaces unpaired surrogates
+/// with U+FFFD.
+///
+/// This struct is created by [`WStr::chars_lossy`].
+#[derive(Clone, Debug)]
+pub struct CharsLossy<'a>(CodePointIndices<'a>);
+
+impl Iterator for CharsLossy<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, x)| {
+            char::from_u32(x).unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl DoubleEndedIterator for CharsLossy<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, x)| {
+            char::from_u32(x).unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+    }
+}
+
+impl WStr {
+    /// Returns an iterator over characters.
+    ///
+    /// An unpaired surrogate is returned as [`UnpairedSurrogate`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    /// let mut it = wstr!("a🍣").chars();
+    /// assert_eq!(it.next(), Some(Ok('a')));
+    /// assert_eq!(it.next(), Some(Ok('🍣')));
+    /// assert_eq!(it.next(), None);
+    /// ```
+    #[inline]
+    pub fn chars(&self) -> Chars<'_> { Chars(self.code_point_indices()) }
+
+    /// Returns an iterator over characters that replaces unpaired surrogates
+    /// with U+FFFD.
+    #[inline]
+    pub fn chars_lossy(&self) -> CharsLossy<'_> {
+        CharsLossy(self.code_point_indices())
+    }
+
+    /// Returns an iterator over characters and their positions in code units.
+    #[inline]
+    pub fn char_indices(&self) -> CharIndices<'_> {
+        CharIndices(self.code_point_indices())
+    }
+
+    /// Returns an iterator over code points.
+    ///
+    /// Unlike [`WStr::chars`], an unpaired surrogate is returned as is.
+    #[inline]
+    pub fn code_points(&self) -> CodePoints<'_> {
+        CodePoints(self.code_point_indices())
+    }
+
+    /// Returns an iterator over code points and their positions in code
+    /// units.
+    #[inline]
+    pub fn code_point_indices(&self) -> CodePointIndices<'_> {
+        let bytes = self.to_bytes();
+        CodePointIndices {
+            bytes,
+            front: 0,
+            back: bytes.len(),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Escaping for `cmd.exe`.
+//!
+//! `cmd.exe` expands `%VAR%` and interprets `&|<>()` before the command
+//! line is passed to a program. [`WStr::cmd_escape`] escapes a command line
+//! with `^`, so that the program receives the original command line.
+use crate::*;
+
+/// An error indicating that a command line cannot be escaped for `cmd.exe`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CmdEscapeError {
+    position: usize,
+}
+
+impl CmdEscapeError {
+    /// Returns the position of the character that cannot be escaped in code
+    /// units.
+    #[inline]
+    pub fn position(&self) -> usize { self.position }
+}
+
+impl fmt::Display for CmdEscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot escape the character at position: {}",
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for CmdEscapeError {}
+
+/// Characters that `cmd.exe` interprets.
+///
+/// `"` in the arguments is escaped too, since `cmd.exe` does not interpret
+/// `^` in quoted blocks.
+const SPECIAL: &[u8] = b"&|<>()\"";
+
+const CARET: u16 = b'^' as u16;
+const QUOTE: u16 = b'"' as u16;
+const PERCENT: u16 = b'%' as u16;
+const EXCLAMATION: u16 = b'!' as u16;
+
+/// Returns the length of the command token, which ends at the first space or
+/// tab outside quotes.
+///
+/// The quotes of the command token are not escaped, so `cmd.exe` finds the
+/// same command token in the escaped command line.
+fn command_len(s: &[u16]) -> usize {
+    let mut quoted = false;
+    s.iter()
+        .position(|&x| {
+            if x == QUOTE {
+                quoted = !quoted;
+            }
+            !quoted && (x == b' ' as u16 || x == b'\t' as u16)
+        })
+        .unwrap_or(s.len())
+}
+
+impl WStr {
+    /// Escapes a command line for `cmd.exe /c`.
+    ///
+    /// `self` should be a command line quoted for the program, such as the
+    /// result of [`WString::join_args`]. The escaped command line is passed
+    /// to `cmd.exe /c` without further quoting, for example, by
+    /// `std::os::windows::process::CommandExt::raw_arg`.
+    ///
+    /// - `&|<>()` and `"` of the arguments are escaped as `^&`.
+    /// - `%` is followed by `^`, so that every name between `%`s starts with
+    ///   `^`, which is not the name of a variable. This also holds for
+    ///   `%VAR:~n,m%` and `%VAR:a=b%`. A trailing `%` is escaped as `^%`.
+    /// - `^` is escaped as `^^`.
+    /// - If `delayed_expansion` is `true`, `cmd.exe` expands `!VAR!` in the
+    ///   command token and in the arguments separately, and only if the
+    ///   token contains `!`. In such a token, `!` is escaped as `^^!`, and
+    ///   `^` is escaped as `^^^^`, since `^` is processed again.
+    ///
+    /// The command token ends at the first space or tab outside quotes. Its
+    /// quotes are not escaped, so that `cmd.exe` runs the same program as
+    /// the quoted program name such as `"C:\Program Files\a.exe"`. In its
+    /// quoted blocks, `cmd.exe` interprets only `%` and `!`, so nothing is
+    /// escaped except that `!` and `^` are escaped as `^!` and `^^` in a
+    /// token with delayed expansion.
+    ///
+    /// If `self` contains a character that cannot be escaped, this function
+    /// returns [`CmdEscapeError`]:
+    ///
+    /// - CR and LF, since they end a command.
+    /// - `%` of the command token in a quoted block, or followed by a quote
+    ///   or the end of the command token, since `^` after it would change
+    ///   the command token that `cmd.exe` finds.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WString, wstr};
+    ///
+    /// let program = wstr!(r"C:\Program Files\a.exe");
+    /// let args = [program, wstr!("a&b"), wstr!("%PATH%")];
+    /// let s = WString::join_args(args).unwrap();
+    /// let s = s.cmd_escape(false).unwrap();
+    /// assert_eq!(
+    ///     s.to_string_lossy(),
+    ///     r#""C:\Program Files\a.exe" a^&b %^PATH^%"#
+    /// );
+    /// ```
+    pub fn cmd_escape(
+        &self,
+        delayed_expansion: bool,
+    ) -> Result<WString, CmdEscapeError> {
+        let s = self.to_bytes();
+        if let Some(position) = s
+            .iter()
+            .position(|&x| x == b'\r' as u16 || x == b'\n' as u16)
+        {
+            return Err(CmdEscapeError { position });
+        }
+        let command_len = command_len(s);
+        let delayed = |t: &[u16]| delayed_expansion && t.contains(&EXCLAMATION);
+        let delayed = [delayed(&s[..command_len]), delayed(&s[command_len..])];
+        let mut v = Vec::with_capacity(s.len() * 2);
+        // Whether the previous character is `%` that needs `^`.
+        let mut after_percent = false;
+        // Whether the character is in a quoted block of the command token.
+        let mut quoted = false;
+        for (i, &x) in s.iter().enumerate() {
+            let command = i < command_len;
+            let delayed = delayed[!command as usize];
+            let last = i + 1 == s.len();
+            if command && x == QUOTE {
+                if after_percent {
+                    return Err(CmdEscapeError { position: i - 1 });
+                }
+                quoted = !quoted;
+                v.push(x);
+                continue;
+            }
+            if quoted {
+                match x {
+                    PERCENT => return Err(CmdEscapeError { position: i }),
+                    CARET if delayed => v.extend_from_slice(&[CARET, CARET]),
+                    EXCLAMATION if delayed => v.extend_from_slice(&[CARET, x]),
+                    _ => v.push(x),
+                }
+                continue;
+            }
+            if x == PERCENT && i + 1 == command_len && !last {
+                return Err(CmdEscapeError { position: i });
+            }
+            let special = x < 0x80 && SPECIAL.contains(&(x as u8));
+            // The escaped character starts with `^` unless it is `%` or an
+            // unescaped character.
+            let starts_with_caret = x == CARET
+                || (delayed && x == EXCLAMATION)
+                || (x == PERCENT && last)
+                || special;
+            if after_percent && !starts_with_caret {
+                v.push(CARET);
+            }
+            after_percent = false;
+            if x == CARET {
+                let n = if delayed { 4 } else { 2 };
+                v.resize(v.len() + n, CARET);
+            } else if delayed && x == EXCLAMATION {
+                v.extend_from_slice(&[CARET, CARET, x]);
+            } else if x == PERCENT {
+                if last {
+                    v.push(CARET);
+                } else {
+                    after_percent = true;
+                }
+                v.push(x);
+            } else {
+                if special {
+                    v.push(CARET);
+                }
+                v.push(x);
+            }
+        }
+        Ok(unsafe { WString::new_unchecked(v) })
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Character boundaries of [`AStr`] in multi-byte code pages.
+//!
+//! Lead-byte ranges are implemented in Rust, so these functions do not
+//! depend on the code pages installed on the system.
+use crate::*;
+
+/// The system default ANSI code page.
+pub const CP_ACP: u32 = 0;
+/// The system default OEM code page.
+pub const CP_OEMCP: u32 = 1;
+/// The ANSI code page of the current thread.
+pub const CP_THREAD_ACP: u32 = 3;
+/// Shift_JIS (Japanese).
+pub const CP_SHIFT_JIS: u32 = 932;
+/// GBK (Simplified Chinese).
+pub const CP_GBK: u32 = 936;
+/// Unified Hangul Code (Korean).
+pub const CP_UHC: u32 = 949;
+/// Big5 (Traditional Chinese).
+pub const CP_BIG5: u32 = 950;
+/// Johab (Korean).
+pub const CP_JOHAB: u32 = 1361;
+/// GB 18030 (Simplified Chinese).
+pub const CP_GB18030: u32 = 54936;
+/// UTF-8.
+pub const CP_UTF8: u32 = 65001;
+
+/// Resolves `CP_ACP`, `CP_OEMCP` and `CP_THREAD_ACP` to the actual code page.
+///
+/// There is no system code page on non-Windows hosts, so they are treated as
+/// a single-byte code page.
+#[inline]
+fn resolve(code_page: u32) -> u32 {
+    #[cfg(windows)]
+    match code_page {
+        CP_ACP | CP_THREAD_ACP => return unsafe { GetACP() },
+        CP_OEMCP => return unsafe { GetOEMCP() },
+        _ => {}
+    }
+    code_page
+}
+
+/// Returns `true` if `x` is a lead byte of a double-byte character.
+fn is_lead_byte(code_page: u32, x: u8) -> bool {
+    match code_page {
+        CP_SHIFT_JIS => matches!(x, 0x81..=0x9F | 0xE0..=0xFC),
+        CP_GBK | CP_UHC | CP_BIG5 | CP_GB18030 => matches!(x, 0x81..=0xFE),
+        CP_JOHAB => matches!(x, 0x84..=0xD3 | 0xD8..=0xDE | 0xE0..=0xF9),
+        _ => false,
+    }
+}
+
+/// Returns the length in bytes of the character at the beginning of `x`.
+///
+/// `x` must not be empty. An incomplete character is treated as a
+/// single-byte character.
+fn char_len(code_page: u32, x: &[u8]) -> usize {
+    let n = match code_page {
+        CP_UTF8 => match x[0] {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 1,
+        },
+        CP_GB18030
+            if is_lead_byte(code_page, x[0])
+                && matches!(x.get(1), Some(0x30..=0x39)) =>
+        {
+            4
+        }
+        _ if is_lead_byte(code_page, x[0]) => 2,
+        _ => 1,
+    };
+    if n > x.len() {
+        return 1;
+    }
+    if code_page == CP_UTF8
+        && !x[1..n].iter().all(|&x| matches!(x, 0x80..=0xBF))
+    {
+        return 1;
+    }
+    n
+}
+
+/// An iterator over characters of [`AStr`] in a code page.
+///
+/// Each character is returned as a sub-slice of bytes.
+///
+/// This struct is created by [`AStr::chars_cp`].
+#[derive(Clone, Debug)]
+pub struct CharsCp<'a> {
+    rest: &'a [u8],
+    code_page: u32,
+}
+
+impl<'a> Iterator for CharsCp<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (c, rest) = self.rest.split_at(char_len(self.code_page, self.rest));
+        self.rest = rest;
+        Some(c)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rest.len().div_ceil(4), Some(self.rest.len()))
+    }
+}
+
+impl AStr {
+    /// Returns an iterator over characters in `code_page`.
+    ///
+    /// Each character is returned as a sub-slice of bytes.
+    /// Shift_JIS, GBK, UHC, Big5, Johab, GB 18030 and UTF-8 are recognized as
+    /// multi-byte code pages, and the others are treated as single-byte code
+    /// pages.
+    /// `CP_ACP`, `CP_OEMCP` and `CP_THREAD_ACP` are resolved on Windows only.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{AStr, CP_SHIFT_JIS};
+    /// // "ソ" (0x83 0x5C) followed by "\".
+    /// let s = AStr::from_bytes_with_nul(b"\x83\x5C\x5C\0").unwrap();
+    /// let mut it = s.chars_cp(CP_SHIFT_JIS);
+    /// assert_eq!(it.next(), Some(&b"\x83\x5C"[..]));
+    /// assert_eq!(it.next(), Some(&b"\x5C"[..]));
+    /// assert_eq!(it.next(), None);
+    /// ```
+    #[inline]
+    pub fn chars_cp(&self, code_page: u32) -> CharsCp<'_> {
+        CharsCp {
+            rest: self.to_bytes(),
+            code_page: resolve(code_page),
+        }
+    }
+
+    /// Returns `true` if `index` is the start of a character in `code_page`.
+    ///
+    /// The start and the end of the string are boundaries.
+    pub fn is_char_boundary(&self, index: usize, code_page: u32) -> bool {
+        self.floor_char_boundary(index, code_page) == index
+    }
+
+    /// Returns the largest character boundary in `code_page` that is less
+    /// than or equal to `index`.
+    ///
+    /// If `index` is greater than the length, the length is returned.
+    pub fn floor_char_boundary(&self, index: usize, code_page: u32) -> usize {
+        let bytes = self.to_bytes();
+        if index >= bytes.len() {
+            return bytes.len();
+        }
+        // A trail byte can be in the range of lead bytes, so the string is
+        // scanned from the start.
+        let code_page = resolve(code_page);
+        let mut i = 0;
+        loop {
+            let n = char_len(code_page, &bytes[i..]);
+            if i + n > index {
+                return i;
+            }
+            i += n;
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl AString {
+    /// Truncates the string to at most `max_bytes` bytes, excluding the null
+    /// terminator, without splitting a character in `code_page`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{AString, CP_SHIFT_JIS};
+    /// // "アイ" in Shift_JIS.
+    /// let mut s = AString::new(b"\x83\x41\x83\x43".to_vec()).unwrap();
+    /// s.truncate_to_fit(3, CP_SHIFT_JIS);
+    /// assert_eq!(s.as_bytes(), b"\x83\x41");
+    /// ```
+    pub fn truncate_to_fit(&mut self, max_bytes: usize, code_page: u32) {
+        if self.as_bytes().len() <= max_bytes {
+            return;
+        }
+        let n = self.floor_char_boundary(max_bytes, code_page);
+        let mut v = self.as_bytes()[..n].to_vec();
+        v.push(0);
+        *self = unsafe { Self::new_nul_unchecked(v) };
+    }
+}
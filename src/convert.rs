@@ -1,31 +1,40 @@
 // Copyright takubokudori.
 // This source code is licensed under the MIT or Apache-2.0 license.
-use crate::{
-    __lib::ptr::{null, null_mut},
-    raw::*,
-};
+#[cfg(windows)]
+use crate::__lib::ptr::{null, null_mut};
+use crate::raw::*;
 
+#[cfg(windows)]
 pub(crate) const CP_ACP: UINT = 0;
+#[cfg(windows)]
 pub(crate) const CP_UTF8: UINT = 65001;
+#[cfg(windows)]
 pub(crate) const MB_ERR_INVALID_CHARS: DWORD = 0x8;
+#[cfg(windows)]
 pub(crate) const WC_ERR_INVALID_CHARS: DWORD = 0x80;
+#[cfg(windows)]
 pub(crate) const WC_NO_BEST_FIT_CHARS: DWORD = 0x400;
+#[cfg(windows)]
 pub(crate) const ERROR_INVALID_PARAMETER: DWORD = 0x57;
+#[cfg(windows)]
 pub(crate) const ERROR_INSUFFICIENT_BUFFER: DWORD = 0x7a;
 pub(crate) const ERROR_NO_UNICODE_TRANSLATION: DWORD = 0x459;
 
 pub(crate) type OsResult<T> = Result<T, u32>;
 
+#[cfg(windows)]
 pub(crate) fn utf8_to_wide_lossy(x: &str) -> OsResult<Vec<u16>> {
     // UTF-8 to Unicode is loss less
     multi_byte_to_wide_char_wrap(CP_UTF8, 0, x.as_bytes())
 }
 
+#[cfg(windows)]
 pub(crate) fn utf8_to_wide(x: &str) -> OsResult<Vec<u16>> {
     // UTF-8 to Unicode is loss less
     multi_byte_to_wide_char_wrap(CP_UTF8, MB_ERR_INVALID_CHARS, x.as_bytes())
 }
 
+#[cfg(windows)]
 pub(crate) fn wide_to_utf8(x: &[u16]) -> OsResult<Vec<u8>> {
     // Unicode to UTF-8 is loss less
     wide_char_to_multi_byte_wrap(
@@ -36,28 +45,62 @@ pub(crate) fn wide_to_utf8(x: &[u16]) -> OsResult<Vec<u8>> {
     )
 }
 
+#[cfg(windows)]
 pub(crate) fn wide_to_utf8_lossy(x: &[u16]) -> OsResult<Vec<u8>> {
     // Unicode to UTF-8 is loss less
     wide_char_to_multi_byte_wrap(CP_UTF8, WC_NO_BEST_FIT_CHARS, x, false)
 }
 
+// On non-Windows hosts, UTF-8 conversions are implemented with `std`.
+// ANSI conversions are not available since they depend on the code page of
+// Windows.
+
+#[cfg(not(windows))]
+pub(crate) fn utf8_to_wide_lossy(x: &str) -> OsResult<Vec<u16>> {
+    Ok(x.encode_utf16().collect())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn utf8_to_wide(x: &str) -> OsResult<Vec<u16>> {
+    Ok(x.encode_utf16().collect())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn wide_to_utf8(x: &[u16]) -> OsResult<Vec<u8>> {
+    let x = if x.is_empty() { &[0] } else { x };
+    String::from_utf16(x)
+        .map(String::into_bytes)
+        .map_err(|_| ERROR_NO_UNICODE_TRANSLATION)
+}
+
+#[cfg(not(windows))]
+pub(crate) fn wide_to_utf8_lossy(x: &[u16]) -> OsResult<Vec<u8>> {
+    let x = if x.is_empty() { &[0] } else { x };
+    Ok(String::from_utf16_lossy(x).into_bytes())
+}
+
+#[cfg(windows)]
 pub(crate) fn mb_to_wide(x: &[u8]) -> OsResult<Vec<u16>> {
     multi_byte_to_wide_char_wrap(CP_ACP, MB_ERR_INVALID_CHARS, x)
 }
 
+#[cfg(windows)]
 pub(crate) fn mb_to_wide_lossy(x: &[u8]) -> OsResult<Vec<u16>> {
     multi_byte_to_wide_char_wrap(CP_ACP, 0, x)
 }
 
+#[cfg(windows)]
 pub(crate) fn wide_to_mb(x: &[u16]) -> OsResult<Vec<u8>> {
     wide_char_to_multi_byte_wrap(CP_ACP, WC_NO_BEST_FIT_CHARS, x, true)
 }
 
+#[cfg(windows)]
 pub(crate) fn wide_to_mb_lossy(x: &[u16]) -> OsResult<Vec<u8>> {
     wide_char_to_multi_byte_wrap(CP_ACP, WC_NO_BEST_FIT_CHARS, x, false)
 }
 
 /// Safe wrapper function of MultiByteToWideChar.
+#[cfg(windows)]
 #[inline(always)]
 fn multi_byte_to_wide_char(
     code_page: UINT,
@@ -87,6 +130,7 @@ fn multi_byte_to_wide_char(
 }
 
 /// Safe wrapper function of WideCharToMultiByte.
+#[cfg(windows)]
 #[inline(always)]
 fn wide_char_to_multi_byte<'a>(
     code_page: UINT,
@@ -120,6 +164,7 @@ fn wide_char_to_multi_byte<'a>(
     }
 }
 
+#[cfg(windows)]
 #[allow(clippy::uninit_vec)]
 pub(crate) fn wide_char_to_multi_byte_wrap(
     code_page: UINT,
@@ -169,6 +214,7 @@ pub(crate) fn wide_char_to_multi_byte_wrap(
 }
 
 /// Gets the required buffer size and gets a multi-byte string.
+#[cfg(windows)]
 #[inline]
 #[allow(clippy::uninit_vec)]
 pub(crate) fn wide_char_to_multi_byte2(
@@ -206,6 +252,7 @@ pub(crate) fn wide_char_to_multi_byte2(
     Ok(ret)
 }
 
+#[cfg(windows)]
 #[allow(clippy::uninit_vec)]
 fn multi_byte_to_wide_char_wrap(
     code_page: UINT,
@@ -238,6 +285,7 @@ fn multi_byte_to_wide_char_wrap(
 }
 
 /// Gets the required buffer size and gets a wide string.
+#[cfg(windows)]
 #[inline]
 #[allow(clippy::uninit_vec)]
 fn multi_byte_to_wide_char2(
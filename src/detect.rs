@@ -0,0 +1,236 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Text encoding detection.
+//!
+//! [`decode_text`] detects the encoding of a text file and decodes it to
+//! [`WString`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use windy::detect::{Encoding, decode_text};
+//!
+//! let (s, enc) = decode_text(b"\xFF\xFEt\0e\0s\0t\0");
+//! assert_eq!(s.to_string_lossy(), "test");
+//! assert_eq!(enc.encoding(), Encoding::Utf16Le);
+//! assert!(enc.has_bom());
+//! ```
+#[cfg(windows)]
+use crate::convert::*;
+use crate::*;
+
+/// An encoding of a text.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Encoding {
+    /// UTF-8.
+    Utf8,
+    /// UTF-16LE.
+    Utf16Le,
+    /// UTF-16BE.
+    Utf16Be,
+    /// The ANSI code page.
+    ///
+    /// On non-Windows hosts, Windows-1252 is used instead since there is no
+    /// ANSI code page.
+    Ansi,
+}
+
+/// The result of encoding detection.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DetectedEncoding {
+    encoding: Encoding,
+    bom: bool,
+    confidence: u8,
+}
+
+impl DetectedEncoding {
+    /// Returns the detected encoding.
+    #[inline]
+    pub fn encoding(&self) -> Encoding { self.encoding }
+
+    /// Returns `true` if the text starts with a BOM.
+    #[inline]
+    pub fn has_bom(&self) -> bool { self.bom }
+
+    /// Returns the confidence of the detection in percent.
+    ///
+    /// The confidence is 100 if the encoding is detected by a BOM, or the
+    /// text consists of ASCII characters only.
+    #[inline]
+    pub fn confidence(&self) -> u8 { self.confidence }
+}
+
+/// The minimum score for which a text without a BOM is detected as UTF-16.
+const UTF16_THRESHOLD: u8 = 60;
+
+/// Detects the encoding of `b` without decoding it.
+///
+/// The encoding is detected by the BOM. If `b` does not start with a BOM,
+/// the encoding is guessed by the following heuristics like
+/// `IsTextUnicode`:
+///
+/// - A text that consists of ASCII characters only is UTF-8.
+/// - A valid UTF-8 text without null bytes is UTF-8.
+/// - Another text without null bytes is ANSI, since a UTF-16 text that
+///   contains ASCII characters such as line breaks has null bytes.
+/// - A text with null bytes is UTF-16 if most of its code units are in
+///   commonly used ranges, and it does not contain illegal code units such
+///   as unpaired surrogates or U+FFFE.
+/// - Otherwise, the text is ANSI.
+pub fn detect(b: &[u8]) -> DetectedEncoding {
+    let bom = |encoding| DetectedEncoding {
+        encoding,
+        bom: true,
+        confidence: 100,
+    };
+    match b {
+        [0xEF, 0xBB, 0xBF, ..] => return bom(Encoding::Utf8),
+        [0xFF, 0xFE, ..] => return bom(Encoding::Utf16Le),
+        [0xFE, 0xFF, ..] => return bom(Encoding::Utf16Be),
+        _ => {}
+    }
+    let no_bom = |encoding, confidence| DetectedEncoding {
+        encoding,
+        bom: false,
+        confidence,
+    };
+    let le = utf16_score(b, ByteOrder::LittleEndian);
+    let be = utf16_score(b, ByteOrder::BigEndian);
+    let (encoding, (score, _)) = if be > le {
+        (Encoding::Utf16Be, be)
+    } else {
+        (Encoding::Utf16Le, le)
+    };
+    if !b.contains(&0) {
+        if b.is_ascii() {
+            return no_bom(Encoding::Utf8, 100);
+        }
+        if let Ok(s) = core::str::from_utf8(b) {
+            // Random bytes rarely form valid multi-byte sequences.
+            let n = s.chars().filter(|c| !c.is_ascii()).count();
+            return no_bom(Encoding::Utf8, (80 + 5 * n).min(99) as u8);
+        }
+        // UTF-16 texts that contain ASCII characters such as line breaks
+        // have null bytes, while DBCS texts often look like CJK characters
+        // in UTF-16.
+        return no_bom(Encoding::Ansi, 100 - score / 2);
+    }
+    if score >= UTF16_THRESHOLD {
+        return no_bom(encoding, score);
+    }
+    // ANSI texts rarely contain null bytes.
+    no_bom(Encoding::Ansi, (100 - score) / 2)
+}
+
+/// Returns `true` if `x` is in a range that is commonly used in texts.
+fn is_common(x: u16) -> bool {
+    matches!(x,
+        0x0009 | 0x000A | 0x000D | 0x0020..=0x007E // ASCII
+        | 0x00A0..=0x024F // Latin
+        | 0x0370..=0x052F // Greek and Cyrillic
+        | 0x0590..=0x06FF // Hebrew and Arabic
+        | 0x0E00..=0x0E7F // Thai
+        | 0x2000..=0x206F // General Punctuation
+        | 0x3000..=0x30FF // CJK Symbols, Hiragana and Katakana
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7A3 // Hangul Syllables
+        | 0xFF00..=0xFFEF // Halfwidth and Fullwidth Forms
+    )
+}
+
+/// Returns the score in percent that `b` is UTF-16 in `order` and the number
+/// of ASCII characters.
+///
+/// The number of ASCII characters breaks a tie, since ASCII characters in
+/// one byte order are often CJK characters in the other byte order.
+fn utf16_score(b: &[u8], order: ByteOrder) -> (u8, usize) {
+    if b.len() & 1 == 1 {
+        return (0, 0);
+    }
+    let v: Vec<u16> = b
+        .chunks_exact(2)
+        .map(|x| order.from_bytes([x[0], x[1]]))
+        .collect();
+    // Trailing null characters are allowed as padding.
+    let len = v.iter().rposition(|&x| x != 0).map_or(0, |i| i + 1);
+    let v = &v[..len];
+    if v.is_empty() {
+        return (0, 0);
+    }
+    let mut common = 0;
+    let mut ascii = 0;
+    for x in char::decode_utf16(v.iter().copied()) {
+        match x {
+            // Null characters, U+FFFE and U+FFFF are not used in texts.
+            Ok('\0' | '\u{FFFE}' | '\u{FFFF}') | Err(_) => return (0, 0),
+            Ok(c) => {
+                if c.is_ascii() {
+                    ascii += 1;
+                }
+                if c as u32 > 0xFFFF || is_common(c as u16) {
+                    common += 1;
+                }
+            }
+        }
+    }
+    ((common * 100 / v.len()) as u8, ascii)
+}
+
+/// Detects the encoding of `b` and decodes it to [`WString`].
+///
+/// A BOM is removed. See [`detect`] for the details of the detection.
+/// The string ends at the first null character.
+pub fn decode_text(b: &[u8]) -> (WString, DetectedEncoding) {
+    let enc = detect(b);
+    let s = match (enc.encoding, enc.bom) {
+        (Encoding::Utf8, bom) => {
+            let b = if bom { &b[3..] } else { b };
+            WString::from_str_lossy(&String::from_utf8_lossy(b))
+        }
+        (Encoding::Utf16Le | Encoding::Utf16Be, bom) => {
+            let b = if bom { &b[2..] } else { b };
+            // Ignore the last byte of an odd length text.
+            let b = &b[..b.len() & !1];
+            let order = if enc.encoding == Encoding::Utf16Le {
+                ByteOrder::LittleEndian
+            } else {
+                ByteOrder::BigEndian
+            };
+            WString::from_utf16_bytes(b, order).unwrap()
+        }
+        (Encoding::Ansi, _) => ansi_to_wstring(b),
+    };
+    (s, enc)
+}
+
+#[cfg(windows)]
+fn ansi_to_wstring(b: &[u8]) -> WString {
+    match mb_to_wide_lossy(b) {
+        Ok(v) => unsafe { WString::new_unchecked(v) },
+        Err(_) => WString::default(),
+    }
+}
+
+/// Characters of Windows-1252 from 0x80 to 0x9F.
+///
+/// Undefined bytes are mapped to the same code points like
+/// `MultiByteToWideChar`.
+#[cfg(not(windows))]
+const WINDOWS_1252: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6,
+    0x2030, 0x0160, 0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018,
+    0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC, 0x2122, 0x0161,
+    0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+#[cfg(not(windows))]
+fn ansi_to_wstring(b: &[u8]) -> WString {
+    let v: Vec<u16> = b
+        .iter()
+        .map(|&x| match x {
+            0x80..=0x9F => WINDOWS_1252[x as usize - 0x80],
+            _ => x as u16,
+        })
+        .collect();
+    unsafe { WString::new_unchecked(v) }
+}
@@ -0,0 +1,253 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Environment blocks.
+//!
+//! An environment block is a list of `name=value` strings terminated by two
+//! null characters, which is passed to `CreateProcessW` and returned by
+//! `GetEnvironmentStringsW`.
+use crate::{__lib::cmp::Ordering, *};
+
+/// An error indicating that the name of an environment variable is invalid.
+///
+/// A name must not be empty, and must not contain `=` except at the
+/// beginning.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InvalidEnvNameError;
+
+impl fmt::Display for InvalidEnvNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid environment variable name")
+    }
+}
+
+impl std::error::Error for InvalidEnvNameError {}
+
+/// Converts `x` to uppercase like `RtlUpcaseUnicodeChar`.
+///
+/// A code unit that has no single uppercase code unit is left unchanged.
+pub(crate) fn upcase(x: u16) -> u16 {
+    if x < 0x80 {
+        return (x as u8).to_ascii_uppercase() as u16;
+    }
+    let Some(c) = char::from_u32(x as u32) else {
+        return x;
+    };
+    let mut u = c.to_uppercase();
+    match (u.next(), u.next()) {
+        (Some(u), None) if (u as u32) <= 0xFFFF => u as u16,
+        _ => x,
+    }
+}
+
+/// Compares `a` and `b` case-insensitively in code unit order.
+pub(crate) fn cmp_ignore_case(a: &[u16], b: &[u16]) -> Ordering {
+    a.iter()
+        .map(|&x| upcase(x))
+        .cmp(b.iter().map(|&x| upcase(x)))
+}
+
+/// Returns `true` if `a` and `b` are equal case-insensitively.
+#[inline]
+pub(crate) fn eq_ignore_case(a: &[u16], b: &[u16]) -> bool {
+    a.len() == b.len() && cmp_ignore_case(a, b) == Ordering::Equal
+}
+
+/// Returns the position of `=` that separates the name and the value.
+///
+/// The first character is a part of the name even if it is `=`, such as
+/// `=C:=C:\Windows`.
+#[inline]
+fn separator(s: &[u16]) -> Option<usize> {
+    Some(s.get(1..)?.iter().position(|&x| x == b'=' as u16)? + 1)
+}
+
+fn is_valid_name(name: &[u16]) -> bool {
+    !name.is_empty() && separator(name).is_none()
+}
+
+/// An ordered list of environment variables.
+///
+/// Names are compared case-insensitively like Windows.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{EnvBlock, WMultiStr, wstr};
+///
+/// let v: Vec<u16> = "Path=C:\\Windows\0=C:=C:\\\0\0".encode_utf16().collect();
+/// let mut env = EnvBlock::parse(WMultiStr::from_bytes_with_nul(&v).unwrap());
+/// assert_eq!(Some(wstr!("C:\\Windows")), env.get(wstr!("PATH")));
+/// assert_eq!(Some(wstr!("C:\\")), env.drive_dir('c'));
+/// env.set(wstr!("TEMP"), wstr!("C:\\Temp")).unwrap();
+/// let block: Vec<u16> = "=C:=C:\\\0Path=C:\\Windows\0TEMP=C:\\Temp\0\0"
+///     .encode_utf16()
+///     .collect();
+/// assert_eq!(block, env.to_block());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EnvBlock {
+    vars: Vec<(WString, WString)>,
+}
+
+impl EnvBlock {
+    /// Creates an empty block.
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Parses an environment block.
+    ///
+    /// The order of the variables is kept. Strings without `=` are ignored.
+    pub fn parse(block: &WMultiStr) -> Self {
+        let vars = block
+            .iter()
+            .filter_map(|s| {
+                let s = s.to_bytes();
+                let i = separator(s)?;
+                unsafe {
+                    Some((
+                        WString::new_unchecked(&s[..i]),
+                        WString::new_unchecked(&s[i + 1..]),
+                    ))
+                }
+            })
+            .collect();
+        Self { vars }
+    }
+
+    /// Creates a block from the environment variables of the current
+    /// process.
+    ///
+    /// Variables that cannot be converted to [`WString`] are ignored.
+    pub fn from_env() -> Self {
+        std::env::vars_os()
+            .filter_map(|(k, v)| {
+                Some((
+                    WString::from_os_str(k).ok()?,
+                    WString::from_os_str(v).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the number of variables.
+    #[inline]
+    pub fn len(&self) -> usize { self.vars.len() }
+
+    /// Returns `true` if the block contains no variables.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.vars.is_empty() }
+
+    fn position(&self, name: &WStr) -> Option<usize> {
+        self.vars
+            .iter()
+            .position(|(k, _)| eq_ignore_case(k.to_bytes(), name.to_bytes()))
+    }
+
+    /// Returns the value of `name`.
+    ///
+    /// If the block has several variables named `name`, the first one is
+    /// returned.
+    pub fn get(&self, name: &WStr) -> Option<&WStr> {
+        self.position(name).map(|i| self.vars[i].1.as_ref())
+    }
+
+    /// Returns the current directory of `drive` stored as `=C:`.
+    pub fn drive_dir(&self, drive: char) -> Option<&WStr> {
+        if !drive.is_ascii_alphabetic() {
+            return None;
+        }
+        let name = [b'=' as u16, drive as u16, b':' as u16, 0];
+        self.get(unsafe { WStr::from_bytes_with_nul_unchecked(&name) })
+    }
+
+    /// Sets `name` to `value`.
+    ///
+    /// If the block already has `name`, its value is replaced and the
+    /// original name is kept.
+    pub fn set(
+        &mut self,
+        name: &WStr,
+        value: &WStr,
+    ) -> Result<(), InvalidEnvNameError> {
+        if !is_valid_name(name.to_bytes()) {
+            return Err(InvalidEnvNameError);
+        }
+        match self.position(name) {
+            Some(i) => self.vars[i].1 = value.to_wstring(),
+            None => self.vars.push((name.to_wstring(), value.to_wstring())),
+        }
+        Ok(())
+    }
+
+    /// Removes `name` and returns its value.
+    pub fn remove(&mut self, name: &WStr) -> Option<WString> {
+        let mut ret = None;
+        while let Some(i) = self.position(name) {
+            ret.get_or_insert(self.vars.remove(i).1);
+        }
+        ret
+    }
+
+    /// Returns an iterator over the names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&WStr, &WStr)> {
+        self.vars.iter().map(|(k, v)| (k.as_ref(), v.as_ref()))
+    }
+
+    /// Sorts the variables in the order that `CreateProcessW` expects.
+    ///
+    /// The variables are sorted by their names case-insensitively in code
+    /// unit order, regardless of the locale. Therefore, `=C:` comes first.
+    pub fn sort(&mut self) {
+        self.vars
+            .sort_by(|a, b| cmp_ignore_case(a.0.to_bytes(), b.0.to_bytes()));
+    }
+
+    /// Serializes the variables in the sorted order.
+    pub fn to_multi_string(&self) -> WMultiString {
+        let mut env = self.clone();
+        env.sort();
+        let mut v = Vec::new();
+        for (k, val) in &env.vars {
+            v.extend_from_slice(k.to_bytes());
+            v.push(b'=' as u16);
+            v.extend_from_slice(val.to_bytes());
+            v.push(0);
+        }
+        WMultiString::from_bytes_lossy(&v)
+    }
+
+    /// Serializes the variables in the sorted order to pass to
+    /// `CreateProcessW` with `CREATE_UNICODE_ENVIRONMENT`.
+    ///
+    /// An empty block is two null characters, since `CreateProcessW` reads
+    /// an empty string and a terminator.
+    pub fn to_block(&self) -> Vec<u16> {
+        let mut v = self
+            .to_multi_string()
+            .into_boxed_bytes_with_nul()
+            .into_vec();
+        if v.len() == 1 {
+            v.push(0);
+        }
+        v
+    }
+}
+
+impl From<&WMultiStr> for EnvBlock {
+    #[inline]
+    fn from(x: &WMultiStr) -> Self { Self::parse(x) }
+}
+
+impl FromIterator<(WString, WString)> for EnvBlock {
+    /// Creates a block from names and values.
+    ///
+    /// Variables with invalid names are ignored, and later values replace
+    /// earlier ones.
+    fn from_iter<I: IntoIterator<Item = (WString, WString)>>(iter: I) -> Self {
+        let mut ret = Self::new();
+        for (k, v) in iter {
+            let _ = ret.set(&k, &v);
+        }
+        ret
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Expansion of `%VAR%` references like `ExpandEnvironmentStringsW`.
+use crate::*;
+
+/// The maximum number of characters of a string that `UNICODE_STRING` can
+/// hold, including the terminating null character.
+pub const UNICODE_STRING_MAX_CHARS: usize = 32767;
+
+/// An error indicating that a string could not be expanded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExpandEnvError {
+    /// The source string has more than
+    /// [`UNICODE_STRING_MAX_CHARS`]` - 1` characters.
+    SourceTooLong,
+    /// The expanded string has more than
+    /// [`UNICODE_STRING_MAX_CHARS`]` - 1` characters.
+    ///
+    /// `required` is the length of the expanded string including the
+    /// terminating null character.
+    ResultTooLong { required: usize },
+}
+
+impl fmt::Display for ExpandEnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SourceTooLong => write!(f, "source string is too long"),
+            Self::ResultTooLong { required } => write!(
+                f,
+                "expanded string is too long: {} characters required",
+                required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExpandEnvError {}
+
+impl WStr {
+    /// Expands `%VAR%` references with `lookup` like
+    /// `ExpandEnvironmentStringsW`.
+    ///
+    /// - `lookup` receives the name between `%`s, and should compare names
+    ///   case-insensitively.
+    /// - If `lookup` returns `None`, `%VAR%` is left intact, and the
+    ///   expansion continues after the closing `%`.
+    /// - `%%` and a `%` without the closing `%` are left intact.
+    /// - Expanded values are not expanded again.
+    /// - The source and the expanded string must be shorter than
+    ///   [`UNICODE_STRING_MAX_CHARS`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WString, wstr};
+    ///
+    /// let lookup = |name: &windy::WStr| {
+    ///     if name.to_string_lossy().eq_ignore_ascii_case("SystemRoot") {
+    ///         Some(WString::from_str("C:\\Windows").unwrap())
+    ///     } else {
+    ///         None
+    ///     }
+    /// };
+    /// let s = wstr!("%systemroot%\\%X%\\%")
+    ///     .expand_env_with(&lookup)
+    ///     .unwrap();
+    /// assert_eq!(s.to_string_lossy(), "C:\\Windows\\%X%\\%");
+    /// ```
+    pub fn expand_env_with(
+        &self,
+        lookup: &impl Fn(&WStr) -> Option<WString>,
+    ) -> Result<WString, ExpandEnvError> {
+        const PERCENT: u16 = b'%' as u16;
+        let mut src = self.to_bytes();
+        if src.len() >= UNICODE_STRING_MAX_CHARS {
+            return Err(ExpandEnvError::SourceTooLong);
+        }
+        let mut v = Vec::with_capacity(src.len());
+        while let Some(i) = src.iter().position(|&x| x == PERCENT) {
+            v.extend_from_slice(&src[..i]);
+            src = &src[i..];
+            let Some(j) = src[1..].iter().position(|&x| x == PERCENT) else {
+                break;
+            };
+            let name = &src[1..j + 1];
+            let value = if name.is_empty() {
+                None
+            } else {
+                lookup(&unsafe { WString::new_unchecked(name) })
+            };
+            match value {
+                Some(value) => v.extend_from_slice(value.to_bytes()),
+                None => v.extend_from_slice(&src[..j + 2]),
+            }
+            src = &src[j + 2..];
+        }
+        v.extend_from_slice(src);
+        if v.len() >= UNICODE_STRING_MAX_CHARS {
+            return Err(ExpandEnvError::ResultTooLong {
+                required: v.len() + 1,
+            });
+        }
+        Ok(unsafe { WString::new_unchecked(v) })
+    }
+
+    /// Expands `%VAR%` references with the variables of `env`.
+    ///
+    /// See [`WStr::expand_env_with`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{EnvBlock, wstr};
+    ///
+    /// let mut env = EnvBlock::new();
+    /// env.set(wstr!("SystemRoot"), wstr!("C:\\Windows")).unwrap();
+    /// let s = wstr!("%SYSTEMROOT%\\System32").expand_env_in(&env).unwrap();
+    /// assert_eq!(s.to_string_lossy(), "C:\\Windows\\System32");
+    /// ```
+    pub fn expand_env_in(
+        &self,
+        env: &EnvBlock,
+    ) -> Result<WString, ExpandEnvError> {
+        self.expand_env_with(&|name| env.get(name).map(WStr::to_wstring))
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+use crate::{
+    __lib::{mem, ops, ptr, ptr::NonNull},
+    raw::*,
+    *,
+};
+use core::ffi::c_void;
+
+/// Frees a buffer allocated by a foreign allocator.
+///
+/// Closures that take `*mut c_void` also implement this trait.
+pub trait Deallocator {
+    /// Frees `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by the allocator that corresponds to this
+    /// deallocator, and must not be freed twice.
+    unsafe fn deallocate(&mut self, ptr: *mut c_void);
+}
+
+impl<F: FnMut(*mut c_void)> Deallocator for F {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_void) { self(ptr) }
+}
+
+/// Deallocator that calls `LocalFree`.
+///
+/// For example, `FormatMessageW` and `ConvertSidToStringSidW` allocate
+/// strings that must be freed by `LocalFree`.
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LocalDeallocator;
+
+#[cfg(windows)]
+impl Deallocator for LocalDeallocator {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_void) {
+        unsafe {
+            LocalFree(ptr);
+        }
+    }
+}
+
+/// Deallocator that calls `CoTaskMemFree`.
+///
+/// For example, `StringFromCLSID` and `SHGetKnownFolderPath` allocate
+/// strings that must be freed by `CoTaskMemFree`.
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CoTaskMemDeallocator;
+
+#[cfg(windows)]
+impl Deallocator for CoTaskMemDeallocator {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_void) {
+        unsafe {
+            CoTaskMemFree(ptr);
+        }
+    }
+}
+
+/// Deallocator that calls `HeapFree`.
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug)]
+pub struct HeapDeallocator {
+    heap: HANDLE,
+}
+
+#[cfg(windows)]
+impl HeapDeallocator {
+    /// Creates a deallocator that frees a buffer in `heap`.
+    ///
+    /// # Safety
+    ///
+    /// `heap` must be a valid heap handle while the deallocator is used.
+    #[inline]
+    pub unsafe fn new(heap: *mut c_void) -> Self { Self { heap } }
+
+    /// Creates a deallocator that frees a buffer in the process heap.
+    #[inline]
+    pub fn process_heap() -> Self {
+        Self {
+            heap: unsafe { GetProcessHeap() },
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Default for HeapDeallocator {
+    #[inline]
+    fn default() -> Self { Self::process_heap() }
+}
+
+#[cfg(windows)]
+impl Deallocator for HeapDeallocator {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_void) {
+        unsafe {
+            HeapFree(self.heap, 0, ptr);
+        }
+    }
+}
+
+/// Represents an owned Unicode string allocated by a foreign allocator.
+///
+/// The string is freed by `D` when it is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(windows)] {
+/// use windy::{ForeignWString, LocalDeallocator};
+///
+/// # unsafe fn format_message() -> *mut u16 { std::ptr::null_mut() }
+/// let ptr: *mut u16 = unsafe { format_message() };
+/// let s = unsafe { ForeignWString::from_raw(ptr, LocalDeallocator) };
+/// println!("{}", s.to_string_lossy());
+/// // `s` is freed by LocalFree here.
+/// # }
+/// ```
+pub struct ForeignWString<D: Deallocator> {
+    ptr: NonNull<wchar_t>,
+    len: usize,
+    dealloc: D,
+}
+
+impl<D: Deallocator> ForeignWString<D> {
+    /// Creates [`ForeignWString`] from `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null, null-terminated Unicode string that can be
+    /// freed by `dealloc`.
+    pub unsafe fn from_raw(ptr: *mut wchar_t, dealloc: D) -> Self {
+        unsafe { Self::from_raw_s_unchecked(ptr, wcslen(ptr), dealloc) }
+    }
+
+    /// Creates [`ForeignWString`] from `ptr` and `len`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null, null-terminated Unicode string that can be
+    /// freed by `dealloc`.
+    pub unsafe fn from_raw_s(
+        ptr: *mut wchar_t,
+        mut len: usize,
+        dealloc: D,
+    ) -> Self {
+        unsafe {
+            let len2 = wcsnlen(ptr, len);
+            if len2 < len {
+                len = len2;
+            }
+            Self::from_raw_s_unchecked(ptr, len, dealloc)
+        }
+    }
+
+    /// Creates [`ForeignWString`] from `ptr` and `len` without length check.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null, null-terminated Unicode string that can be
+    /// freed by `dealloc`.
+    #[inline]
+    pub unsafe fn from_raw_s_unchecked(
+        ptr: *mut wchar_t,
+        len: usize,
+        dealloc: D,
+    ) -> Self {
+        Self {
+            ptr: unsafe { NonNull::new_unchecked(ptr) },
+            len,
+            dealloc,
+        }
+    }
+
+    /// Returns &[`WStr`].
+    #[inline]
+    pub fn as_c_str(&self) -> &WStr { self }
+
+    /// Returns the deallocator.
+    #[inline]
+    pub fn deallocator(&self) -> &D { &self.dealloc }
+
+    /// Consumes [`ForeignWString`] and returns the pointer without freeing
+    /// it.
+    pub fn into_raw(self) -> *mut wchar_t {
+        let mut this = mem::ManuallyDrop::new(self);
+        unsafe { ptr::drop_in_place(&mut this.dealloc) };
+        this.ptr.as_ptr()
+    }
+}
+
+impl<D: Deallocator> ops::Deref for ForeignWString<D> {
+    type Target = WStr;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { WStr::from_raw_s_unchecked(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<D: Deallocator> AsRef<WStr> for ForeignWString<D> {
+    #[inline]
+    fn as_ref(&self) -> &WStr { self }
+}
+
+impl<D: Deallocator> Drop for ForeignWString<D> {
+    fn drop(&mut self) {
+        unsafe {
+            self.dealloc.deallocate(self.ptr.as_ptr() as *mut c_void);
+        }
+    }
+}
+
+impl<D: Deallocator> PartialEq for ForeignWString<D> {
+    fn eq(&self, other: &Self) -> bool { (**self).eq(&**other) }
+}
+
+impl<D: Deallocator> Eq for ForeignWString<D> {}
+
+impl<D: Deallocator> PartialEq<WStr> for ForeignWString<D> {
+    fn eq(&self, other: &WStr) -> bool { (**self).eq(other) }
+}
+
+impl<D: Deallocator> fmt::Debug for ForeignWString<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// The string is owned by `ForeignWString`.
+unsafe impl<D: Deallocator + Send> Send for ForeignWString<D> {}
+unsafe impl<D: Deallocator + Sync> Sync for ForeignWString<D> {}
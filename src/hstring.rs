@@ -0,0 +1,438 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! HSTRING strings.
+//!
+//! An HSTRING is an immutable Unicode string of WinRT. It is a pointer to
+//! a header that points to the characters. A null HSTRING is an empty
+//! string.
+//!
+//! - A heap-allocated HSTRING has a reference count after the header, and
+//!   the characters follow it.
+//! - A fast-pass HSTRING (string reference) has a header allocated by the
+//!   caller, typically on the stack, that points to the caller's buffer. It
+//!   is not reference-counted, and is duplicated when it is copied.
+#[cfg(feature = "std")]
+use crate::__lib::{hash, mem, ops, sync::atomic};
+use crate::{
+    __lib::{marker::PhantomData, ptr},
+    raw::c_void,
+    *,
+};
+
+/// The flag of a fast-pass HSTRING.
+const HSTRING_REFERENCE_FLAG: u32 = 1;
+
+/// Represents `HSTRING_HEADER`.
+///
+/// The size is 24 bytes on 64-bit targets and 20 bytes on 32-bit targets,
+/// which is the same as `HSTRING_HEADER` of the Windows SDK.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct HStringHeader {
+    flags: u32,
+    len: u32,
+    _0: u32,
+    _1: u32,
+    data: *const u16,
+}
+
+impl HStringHeader {
+    /// Returns `true` if the header is a fast-pass HSTRING.
+    #[inline]
+    pub fn is_reference(&self) -> bool {
+        self.flags & HSTRING_REFERENCE_FLAG != 0
+    }
+
+    /// Returns the length in code units without the null terminator.
+    #[inline]
+    pub fn len(&self) -> u32 { self.len }
+
+    /// Returns `true` if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns the string that the header points to.
+    ///
+    /// # Safety
+    ///
+    /// `self.data` must point to a valid null-terminated string.
+    #[cfg(feature = "std")]
+    #[inline]
+    unsafe fn as_wstr(&self) -> &WStr {
+        unsafe { WStr::from_raw_s_unchecked(self.data, self.len as usize) }
+    }
+}
+
+/// A heap-allocated HSTRING.
+#[cfg(feature = "std")]
+#[repr(C)]
+struct HStringInner {
+    header: HStringHeader,
+    count: atomic::AtomicU32,
+    buffer_start: u16,
+}
+
+#[cfg(feature = "std")]
+impl HStringInner {
+    /// Returns the size in bytes of a heap-allocated HSTRING of `len` code
+    /// units.
+    ///
+    /// `buffer_start` holds the null terminator.
+    #[inline]
+    fn size(len: usize) -> usize { mem::size_of::<Self>() + 2 * len }
+
+    /// Allocates an uninitialized HSTRING of `len` code units.
+    #[cfg(windows)]
+    fn allocate(len: usize) -> *mut Self {
+        unsafe { HeapAlloc(GetProcessHeap(), 0, Self::size(len)) as *mut Self }
+    }
+
+    /// Frees `p`.
+    #[cfg(windows)]
+    unsafe fn free(p: *mut Self) {
+        unsafe {
+            HeapFree(GetProcessHeap(), 0, p as *mut c_void);
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn layout(len: usize) -> std::alloc::Layout {
+        std::alloc::Layout::from_size_align(
+            Self::size(len),
+            mem::align_of::<Self>(),
+        )
+        .unwrap()
+    }
+
+    /// Allocates an uninitialized HSTRING of `len` code units.
+    #[cfg(not(windows))]
+    fn allocate(len: usize) -> *mut Self {
+        unsafe { std::alloc::alloc(Self::layout(len)) as *mut Self }
+    }
+
+    /// Frees `p`.
+    #[cfg(not(windows))]
+    unsafe fn free(p: *mut Self) {
+        unsafe {
+            let len = (*p).header.len as usize;
+            std::alloc::dealloc(p as *mut u8, Self::layout(len));
+        }
+    }
+
+    /// Creates a heap-allocated HSTRING from `s`.
+    fn new(s: &[u16]) -> *mut Self {
+        assert!(s.len() <= u32::MAX as usize, "HSTRING is too long");
+        unsafe {
+            let p = Self::allocate(s.len());
+            assert!(!p.is_null(), "failed to allocate HSTRING");
+            let data = ptr::addr_of_mut!((*p).buffer_start);
+            ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+            data.add(s.len()).write(0);
+            ptr::addr_of_mut!((*p).header).write(HStringHeader {
+                flags: 0,
+                len: s.len() as u32,
+                _0: 0,
+                _1: 0,
+                data,
+            });
+            ptr::addr_of_mut!((*p).count).write(atomic::AtomicU32::new(1));
+            p
+        }
+    }
+}
+
+/// Represents an owned HSTRING.
+///
+/// [`HString`] is immutable and reference-counted, so cloning is cheap.
+///
+/// Converting `&str` to [`HString`] truncates the string at the first null
+/// character, so `HString::from("a\0b")` is `a`.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{HString, WString};
+///
+/// let s = HString::from("test");
+/// let t = s.clone(); // Increments the reference count.
+/// assert_eq!(t.to_string_lossy(), "test");
+/// let raw = s.into_raw(); // Passes to WinRT.
+/// let s = unsafe { HString::from_raw(raw) };
+/// ```
+#[cfg(feature = "std")]
+#[repr(transparent)]
+pub struct HString {
+    ptr: *mut HStringHeader,
+}
+
+#[cfg(feature = "std")]
+impl HString {
+    /// Creates an empty [`HString`].
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+        }
+    }
+
+    /// Creates [`HString`] from `s`.
+    ///
+    /// An empty string is a null HSTRING, like `WindowsCreateString`.
+    pub fn from_wstr(s: &WStr) -> Self {
+        if s.to_bytes().is_empty() {
+            return Self::new();
+        }
+        Self {
+            ptr: HStringInner::new(s.to_bytes()) as *mut HStringHeader,
+        }
+    }
+
+    /// Creates [`HString`] from an HSTRING.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or a valid HSTRING, and the ownership of it is
+    /// moved to [`HString`].
+    #[inline]
+    pub unsafe fn from_raw(ptr: *mut c_void) -> Self {
+        Self {
+            ptr: ptr as *mut HStringHeader,
+        }
+    }
+
+    /// Returns the HSTRING.
+    ///
+    /// The ownership is not moved.
+    #[inline]
+    pub fn as_raw(&self) -> *mut c_void { self.ptr as *mut c_void }
+
+    /// Consumes [`HString`] and returns the HSTRING without releasing it.
+    #[inline]
+    pub fn into_raw(self) -> *mut c_void {
+        mem::ManuallyDrop::new(self).as_raw()
+    }
+
+    /// Returns the header, or `None` if the string is empty.
+    #[inline]
+    pub fn header(&self) -> Option<&HStringHeader> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns the reference count, or `None` if the string is not
+    /// reference-counted.
+    pub fn ref_count(&self) -> Option<u32> {
+        let header = self.header()?;
+        if header.is_reference() {
+            return None;
+        }
+        let inner = self.ptr as *const HStringInner;
+        Some(unsafe { (*inner).count.load(atomic::Ordering::Relaxed) })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clone for HString {
+    fn clone(&self) -> Self {
+        match self.header() {
+            None => Self::new(),
+            // A fast-pass HSTRING is duplicated.
+            Some(x) if x.is_reference() => Self::from_wstr(self),
+            Some(_) => {
+                let inner = self.ptr as *const HStringInner;
+                unsafe {
+                    (*inner).count.fetch_add(1, atomic::Ordering::Relaxed)
+                };
+                Self { ptr: self.ptr }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for HString {
+    fn drop(&mut self) {
+        match self.header() {
+            None => {}
+            Some(x) if x.is_reference() => {}
+            Some(_) => unsafe {
+                let inner = self.ptr as *mut HStringInner;
+                if (*inner).count.fetch_sub(1, atomic::Ordering::Release) == 1 {
+                    atomic::fence(atomic::Ordering::Acquire);
+                    HStringInner::free(inner);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ops::Deref for HString {
+    type Target = WStr;
+
+    fn deref(&self) -> &Self::Target {
+        match self.header() {
+            Some(x) => unsafe { x.as_wstr() },
+            None => <&WStr>::default(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsRef<WStr> for HString {
+    #[inline]
+    fn as_ref(&self) -> &WStr { self }
+}
+
+#[cfg(feature = "std")]
+impl Default for HString {
+    #[inline]
+    fn default() -> Self { Self::new() }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for HString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool { (**self).eq(&**other) }
+}
+
+#[cfg(feature = "std")]
+impl Eq for HString {}
+
+#[cfg(feature = "std")]
+impl PartialEq<WStr> for HString {
+    #[inline]
+    fn eq(&self, other: &WStr) -> bool { (**self).eq(other) }
+}
+
+#[cfg(feature = "std")]
+impl hash::Hash for HString {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) { (**self).hash(state) }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for HString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&WStr> for HString {
+    #[inline]
+    fn from(x: &WStr) -> Self { Self::from_wstr(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&WString> for HString {
+    #[inline]
+    fn from(x: &WString) -> Self { Self::from_wstr(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<WString> for HString {
+    #[inline]
+    fn from(x: WString) -> Self { Self::from_wstr(&x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&str> for HString {
+    /// Converts `x` to [`HString`].
+    ///
+    /// The string ends at the first null character of `x`, and the rest of
+    /// `x` is dropped.
+    fn from(x: &str) -> Self {
+        let v: Vec<u16> = x.encode_utf16().take_while(|&x| x != 0).collect();
+        if v.is_empty() {
+            return Self::new();
+        }
+        Self {
+            ptr: HStringInner::new(&v) as *mut HStringHeader,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&HStringReference<'_>> for HString {
+    #[inline]
+    fn from(x: &HStringReference<'_>) -> Self { Self::from_wstr(x.as_wstr()) }
+}
+
+// The reference count is atomic.
+#[cfg(feature = "std")]
+unsafe impl Send for HString {}
+#[cfg(feature = "std")]
+unsafe impl Sync for HString {}
+
+/// Represents a fast-pass HSTRING.
+///
+/// The header is stored in [`HStringReference`], and points to the
+/// borrowed string without copying. It works like
+/// `WindowsCreateStringReference`.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{HStringReference, wstr};
+///
+/// let r = HStringReference::new(wstr!("Windows.Foundation.Uri"));
+/// let hstring = r.as_raw(); // Valid while `r` is alive and not moved.
+/// ```
+pub struct HStringReference<'a> {
+    header: HStringHeader,
+    _marker: PhantomData<&'a WStr>,
+}
+
+impl<'a> HStringReference<'a> {
+    /// Creates a fast-pass HSTRING that points to `s`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is longer than `u32::MAX`.
+    pub fn new(s: &'a WStr) -> Self {
+        let len = s.to_bytes().len();
+        assert!(len <= u32::MAX as usize, "HSTRING is too long");
+        Self {
+            header: HStringHeader {
+                flags: HSTRING_REFERENCE_FLAG,
+                len: len as u32,
+                _0: 0,
+                _1: 0,
+                data: s.as_ptr(),
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the header.
+    #[inline]
+    pub fn header(&self) -> &HStringHeader { &self.header }
+
+    /// Returns the HSTRING.
+    ///
+    /// An empty string is a null HSTRING. The HSTRING must not be used after
+    /// `self` is moved or dropped.
+    pub fn as_raw(&self) -> *mut c_void {
+        if self.header.is_empty() {
+            return ptr::null_mut();
+        }
+        &self.header as *const HStringHeader as *mut c_void
+    }
+
+    /// Returns the string.
+    #[inline]
+    pub fn as_wstr(&self) -> &'a WStr {
+        unsafe {
+            WStr::from_raw_s_unchecked(
+                self.header.data,
+                self.header.len as usize,
+            )
+        }
+    }
+}
+
+impl fmt::Debug for HStringReference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_wstr(), f)
+    }
+}
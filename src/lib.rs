@@ -15,7 +15,25 @@
 //! - UnicodeString(UNICODE_STRING)
 //! - Interconversion between AString, WString and String.
 //! - no_std support
+//! - Non-Windows hosts support
 //! - Macros support
+//! - Foreign allocated strings (ForeignWString)
+//! - Searching and slicing (find, split, trim, replace, ...)
+//! - Surrogate-aware character iteration
+//! - DBCS-aware character boundaries of ANSI strings
+//! - Lossless WTF-8 conversion (Wtf8, Wtf8Buf)
+//! - Interconversion between WString, OsString and PathBuf
+//! - UTF-16LE/UTF-16BE byte conversions
+//! - Text encoding detection (detect::decode_text)
+//! - BSTR (BString, BStr)
+//! - HSTRING (HString, HStringReference)
+//! - Double-null-terminated string lists (WMultiString, AMultiString)
+//! - Environment blocks (EnvBlock)
+//! - `%VAR%` expansion (WStr::expand_env_with)
+//! - Command line parsing and quoting (WStr::parse_args, WString::join_args)
+//! - Escaping for cmd.exe (WStr::cmd_escape)
+//! - Windows paths on any host (WPath, WPathBuf)
+//! - Conversions between Win32, verbatim and NT paths (WStr::to_nt_path)
 //!
 //! # Installation
 //!
@@ -32,49 +50,110 @@
 //!
 //! AString and WString are not available when no_std.
 //!
+//! # Non-Windows hosts
+//!
+//! Windy can be built on non-Windows hosts to handle Windows strings, for
+//! example, on Linux build servers.
+//! Conversions between UTF-8 and Unicode strings are implemented in Rust on
+//! such hosts. Conversions from/to ANSI strings are only available on Windows
+//! since they depend on the ANSI code page of the system.
+//!
 //! # Macros support
 //!
-//! [windy-macros](https://github.com/takubokudori/windy-macros) to convert a UTF-8 string to WString or AString at compile
-//! time.
+//! [`wstr!`] and [`astr!`] convert a string literal to `&'static WStr` or
+//! `&'static AStr` at compile time.
 //!
-//! ```toml
-//! [dependencies]
-//! windy = "0.3.1"
-//! windy-macros = "0.2.0"
+//! ```no_run
+//! use windy::{AStr, WStr, astr, wstr};
+//!
+//! let w: &WStr = wstr!("test🍣");
+//! let a: &AStr = astr!("test");
 //! ```
 //!
 //! # License
 //!
 //! This software is released under the MIT or Apache-2.0 License, see LICENSE-MIT or LICENSE-APACHE.
-#![cfg(windows)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "std")]
+mod args;
+mod bstr;
+mod chars;
+#[cfg(feature = "std")]
+mod cmd;
+mod codepage;
 #[cfg(feature = "std")]
 mod convert;
+#[cfg(feature = "std")]
+pub mod detect;
+#[cfg(feature = "std")]
+mod env_block;
+#[cfg(feature = "std")]
+mod expand;
+mod foreign;
+mod hstring;
+mod macros;
+mod multi_str;
+#[cfg(feature = "std")]
+mod nt_path;
 mod ntstring;
+#[cfg(feature = "std")]
+mod os_str;
+#[cfg(feature = "std")]
+mod path;
+mod pattern;
 mod raw;
 #[cfg(feature = "std")]
 mod string;
 #[cfg(feature = "std")]
 pub mod traits;
 mod windy_str;
+mod wtf8;
 
+#[cfg(feature = "std")]
+pub use args::*;
+pub use bstr::*;
+pub use chars::*;
+#[cfg(feature = "std")]
+pub use cmd::*;
+pub use codepage::*;
+#[cfg(feature = "std")]
+pub use env_block::*;
+#[cfg(feature = "std")]
+pub use expand::*;
+pub use foreign::*;
+pub use hstring::*;
+#[doc(hidden)]
+pub use macros::__private;
+pub use multi_str::*;
+#[cfg(feature = "std")]
+pub use nt_path::*;
 pub use ntstring::*;
+#[cfg(feature = "std")]
+pub use os_str::*;
+#[cfg(feature = "std")]
+pub use path::*;
+pub use pattern::*;
 use raw::*;
 #[cfg(feature = "std")]
 pub use string::*;
 pub use windy_str::*;
+pub use wtf8::*;
 
 #[cfg(not(feature = "std"))]
 #[allow(unused_imports)]
 pub(crate) mod __lib {
-    pub(crate) use core::{cmp, convert, fmt, ops, ptr, slice};
+    pub(crate) use core::{
+        cmp, convert, fmt, hash, marker, mem, ops, ptr, slice, sync,
+    };
 }
 
 #[cfg(feature = "std")]
 #[allow(unused_imports)]
 pub(crate) mod __lib {
-    pub(crate) use std::{cmp, convert, fmt, ops, ptr, slice};
+    pub(crate) use std::{
+        cmp, convert, fmt, hash, marker, mem, ops, ptr, slice, sync,
+    };
 }
 
 use __lib::fmt;
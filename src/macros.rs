@@ -0,0 +1,155 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+
+/// Converts a string literal to `&'static WStr` at compile time.
+///
+/// The returned string is null-terminated.
+/// A literal that contains a null character is rejected at compile time.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{UnicodeString, WStr, wstr};
+///
+/// static NAME: UnicodeString<'static> =
+///     UnicodeString::from_static(wstr!("\\Device\\Null"));
+///
+/// let s: &WStr = wstr!("test🍣");
+/// assert_eq!(s.to_bytes(), [0x74, 0x65, 0x73, 0x74, 0xD83C, 0xDF63]);
+/// ```
+///
+/// ```compile_fail
+/// let s = windy::wstr!("te\0st");
+/// ```
+#[macro_export]
+macro_rules! wstr {
+    ($s:expr) => {{
+        const __WINDY_S: &str = $s;
+        const __WINDY_N: usize = $crate::__private::utf16_len(__WINDY_S) + 1;
+        const __WINDY_W: [u16; __WINDY_N] =
+            $crate::__private::encode_utf16::<__WINDY_N>(__WINDY_S);
+        const __WINDY_R: &$crate::WStr =
+            unsafe { $crate::WStr::from_bytes_with_nul_unchecked(&__WINDY_W) };
+        __WINDY_R
+    }};
+}
+
+/// Converts a string literal to `&'static AStr` at compile time.
+///
+/// The returned string is null-terminated.
+/// A literal that contains a null character or a non-ASCII character is
+/// rejected at compile time, since the meaning of non-ASCII bytes depends on
+/// the ANSI code page at runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{AStr, astr};
+///
+/// let s: &AStr = astr!("test");
+/// assert_eq!(s.to_bytes_with_nul(), b"test\0");
+/// ```
+///
+/// ```compile_fail
+/// let s = windy::astr!("スペシャル");
+/// ```
+#[macro_export]
+macro_rules! astr {
+    ($s:expr) => {{
+        const __WINDY_S: &str = $s;
+        const __WINDY_N: usize = __WINDY_S.len() + 1;
+        const __WINDY_A: [u8; __WINDY_N] =
+            $crate::__private::encode_ascii::<__WINDY_N>(__WINDY_S);
+        const __WINDY_R: &$crate::AStr =
+            unsafe { $crate::AStr::from_bytes_with_nul_unchecked(&__WINDY_A) };
+        __WINDY_R
+    }};
+}
+
+#[doc(hidden)]
+pub mod __private {
+    /// Decodes a UTF-8 character at `i` and returns the code point and its
+    /// length in bytes.
+    ///
+    /// `b` must be a valid UTF-8 string.
+    const fn decode_utf8(b: &[u8], i: usize) -> (u32, usize) {
+        let x = b[i] as u32;
+        if x < 0x80 {
+            (x, 1)
+        } else if x < 0xE0 {
+            ((x & 0x1F) << 6 | (b[i + 1] as u32 & 0x3F), 2)
+        } else if x < 0xF0 {
+            (
+                (x & 0x0F) << 12
+                    | (b[i + 1] as u32 & 0x3F) << 6
+                    | (b[i + 2] as u32 & 0x3F),
+                3,
+            )
+        } else {
+            (
+                (x & 0x07) << 18
+                    | (b[i + 1] as u32 & 0x3F) << 12
+                    | (b[i + 2] as u32 & 0x3F) << 6
+                    | (b[i + 3] as u32 & 0x3F),
+                4,
+            )
+        }
+    }
+
+    /// Returns the number of UTF-16 code units of `s` without a null
+    /// terminator.
+    pub const fn utf16_len(s: &str) -> usize {
+        let b = s.as_bytes();
+        let mut i = 0;
+        let mut n = 0;
+        while i < b.len() {
+            let (c, l) = decode_utf8(b, i);
+            n += if c >= 0x10000 { 2 } else { 1 };
+            i += l;
+        }
+        n
+    }
+
+    /// Encodes `s` to a null-terminated UTF-16 array.
+    ///
+    /// `N` must be `utf16_len(s) + 1`.
+    pub const fn encode_utf16<const N: usize>(s: &str) -> [u16; N] {
+        let b = s.as_bytes();
+        let mut ret = [0u16; N];
+        let mut i = 0;
+        let mut j = 0;
+        while i < b.len() {
+            let (c, l) = decode_utf8(b, i);
+            assert!(c != 0, "wstr! does not accept a null character");
+            if c >= 0x10000 {
+                let c = c - 0x10000;
+                ret[j] = 0xD800 | (c >> 10) as u16;
+                ret[j + 1] = 0xDC00 | (c & 0x3FF) as u16;
+                j += 2;
+            } else {
+                ret[j] = c as u16;
+                j += 1;
+            }
+            i += l;
+        }
+        assert!(j + 1 == N, "N must be utf16_len(s) + 1");
+        ret
+    }
+
+    /// Copies `s` to a null-terminated ASCII array.
+    ///
+    /// `N` must be `s.len() + 1`.
+    pub const fn encode_ascii<const N: usize>(s: &str) -> [u8; N] {
+        let b = s.as_bytes();
+        assert!(b.len() + 1 == N, "N must be s.len() + 1");
+        let mut ret = [0u8; N];
+        let mut i = 0;
+        while i < b.len() {
+            assert!(b[i] != 0, "astr! does not accept a null character");
+            assert!(b[i] < 0x80, "astr! accepts only ASCII characters");
+            ret[i] = b[i];
+            i += 1;
+        }
+        ret
+    }
+}
@@ -0,0 +1,443 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Double-null-terminated string lists.
+//!
+//! A multi-string is a sequence of null-terminated strings followed by an
+//! additional null character, such as `a\0bc\0\0`.
+//! It is used by `REG_MULTI_SZ` values, `GetLogicalDriveStringsW`, the
+//! `lpstrFilter` member of `OPENFILENAMEW` and environment blocks.
+//!
+//! An empty list is represented by a single null character, since the
+//! list cannot contain empty strings.
+use crate::{
+    __lib::hash::{Hash, Hasher},
+    *,
+};
+#[cfg(feature = "std")]
+use std::{borrow::Borrow, ops};
+
+/// An error indicating that a multi-string is malformed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MultiStrError {
+    /// The slice is not terminated by two null characters.
+    NotDoubleNulTerminated,
+    /// The slice has two null characters at `position` before its end.
+    InteriorDoubleNul { position: usize },
+    /// The string at `index` contains a null character.
+    InteriorNul { index: usize },
+    /// The string at `index` is empty.
+    ///
+    /// An empty string cannot be stored since it terminates the list.
+    EmptyString { index: usize },
+}
+
+impl fmt::Display for MultiStrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotDoubleNulTerminated => {
+                write!(f, "data provided is not double nul terminated")
+            }
+            Self::InteriorDoubleNul { position } => write!(
+                f,
+                "data provided contains an interior double nul at position: {}",
+                position
+            ),
+            Self::InteriorNul { index } => {
+                write!(f, "nul byte found in the string at index: {}", index)
+            }
+            Self::EmptyString { index } => {
+                write!(f, "empty string found at index: {}", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MultiStrError {}
+
+/// Returns the length of the list at the beginning of `v` including the
+/// terminator, or `None` if the list is not terminated.
+fn list_len<T: Copy + Default + PartialEq>(v: &[T]) -> Option<usize> {
+    let nul = T::default();
+    let mut i = 0;
+    loop {
+        if *v.get(i)? == nul {
+            return Some(i + 1);
+        }
+        i += v[i..].iter().position(|&x| x == nul)? + 1;
+    }
+}
+
+/// Splits `v` into strings like `RegGetValueW`.
+///
+/// The list ends at the first empty string or the end of `v`, so a missing
+/// terminator is tolerated.
+#[cfg(feature = "std")]
+fn lossy_strs<T: Copy + Default + PartialEq>(
+    v: &[T],
+) -> impl Iterator<Item = &[T]> {
+    let nul = T::default();
+    v.split(move |&x| x == nul).take_while(|x| !x.is_empty())
+}
+
+macro_rules! multi_str_impl {
+    ($ms:ident, $mo:ident, $it:ident, $s:ident, $t:ty) => {
+        /// A borrowed double-null-terminated string list.
+        #[repr(transparent)]
+        pub struct $ms {
+            inner: [$t],
+        }
+
+        impl $ms {
+            /// Creates a list from `bytes`, which must consist of
+            /// null-terminated non-empty strings and a terminating null
+            /// character.
+            ///
+            /// An empty slice is an empty list. `[0, 0]`, which some APIs
+            /// return for an empty list, is an empty list too.
+            pub fn from_bytes_with_nul(
+                bytes: &[$t],
+            ) -> Result<&Self, MultiStrError> {
+                if bytes.is_empty() || bytes == [0, 0] {
+                    return Ok(<&Self>::default());
+                }
+                match list_len(bytes) {
+                    Some(n) if n == bytes.len() => {
+                        Ok(unsafe { Self::from_bytes_with_nul_unchecked(bytes) })
+                    }
+                    Some(n) => Err(MultiStrError::InteriorDoubleNul {
+                        position: n - 1,
+                    }),
+                    None => Err(MultiStrError::NotDoubleNulTerminated),
+                }
+            }
+
+            /// Creates a list from `bytes` up to the first two consecutive
+            /// null characters.
+            ///
+            /// The data after the terminator is ignored. An empty slice is
+            /// an empty list.
+            pub fn from_bytes_until_double_nul(
+                bytes: &[$t],
+            ) -> Result<&Self, MultiStrError> {
+                if bytes.is_empty() {
+                    return Ok(<&Self>::default());
+                }
+                match list_len(bytes) {
+                    Some(n) => Ok(unsafe {
+                        Self::from_bytes_with_nul_unchecked(&bytes[..n])
+                    }),
+                    None => Err(MultiStrError::NotDoubleNulTerminated),
+                }
+            }
+
+            /// Creates a list from `bytes` without any checks.
+            ///
+            /// # Safety
+            ///
+            /// `bytes` must be a correct double-null-terminated string list.
+            #[inline]
+            pub const unsafe fn from_bytes_with_nul_unchecked(
+                bytes: &[$t],
+            ) -> &Self {
+                unsafe { &*(bytes as *const [$t] as *const Self) }
+            }
+
+            /// Creates a list from `ptr`.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must be a double-null-terminated string list.
+            pub unsafe fn from_raw<'a>(ptr: *const $t) -> &'a Self {
+                unsafe {
+                    let mut p = ptr;
+                    loop {
+                        let n = $s::from_raw(p).to_bytes_with_nul().len();
+                        p = p.add(n);
+                        if n == 1 {
+                            break;
+                        }
+                    }
+                    let len = p.offset_from(ptr) as usize;
+                    Self::from_bytes_with_nul_unchecked(
+                        core::slice::from_raw_parts(ptr, len),
+                    )
+                }
+            }
+
+            /// Returns a pointer to the list.
+            #[inline]
+            pub const fn as_ptr(&self) -> *const $t { self.inner.as_ptr() }
+
+            /// Returns the list including the terminating null characters.
+            #[inline]
+            pub const fn as_bytes_with_nul(&self) -> &[$t] { &self.inner }
+
+            /// Returns `true` if the list contains no strings.
+            #[inline]
+            pub fn is_empty(&self) -> bool { self.inner.len() == 1 }
+
+            /// Returns an iterator over the strings.
+            #[inline]
+            pub fn iter(&self) -> $it<'_> { $it { rest: &self.inner } }
+
+            /// Converts the list to an owned list.
+            #[cfg(feature = "std")]
+            #[inline]
+            pub fn to_multi_string(&self) -> $mo { $mo::from(self) }
+        }
+
+        impl Default for &$ms {
+            #[inline]
+            fn default() -> Self {
+                const EMPTY: &[$t] = &[0];
+                unsafe { $ms::from_bytes_with_nul_unchecked(EMPTY) }
+            }
+        }
+
+        impl PartialEq for $ms {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool { self.inner == other.inner }
+        }
+
+        impl Eq for $ms {}
+
+        impl Hash for $ms {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) { self.inner.hash(state) }
+        }
+
+        impl fmt::Debug for $ms {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a> IntoIterator for &'a $ms {
+            type IntoIter = $it<'a>;
+            type Item = &'a $s;
+
+            #[inline]
+            fn into_iter(self) -> $it<'a> { self.iter() }
+        }
+
+        #[doc = concat!("An iterator over the strings of [`", stringify!($ms), "`].")]
+        #[derive(Clone, Debug)]
+        pub struct $it<'a> {
+            rest: &'a [$t],
+        }
+
+        impl<'a> Iterator for $it<'a> {
+            type Item = &'a $s;
+
+            fn next(&mut self) -> Option<&'a $s> {
+                let n = self.rest.iter().position(|&x| x == 0)?;
+                if n == 0 {
+                    return None;
+                }
+                let (s, rest) = self.rest.split_at(n + 1);
+                self.rest = rest;
+                Some(unsafe { $s::from_bytes_with_nul_unchecked(s) })
+            }
+        }
+
+        impl core::iter::FusedIterator for $it<'_> {}
+
+        /// An owned double-null-terminated string list.
+        #[cfg(feature = "std")]
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        pub struct $mo {
+            inner: Vec<$t>,
+        }
+
+        #[cfg(feature = "std")]
+        impl $mo {
+            /// Creates an empty list.
+            #[inline]
+            pub fn new() -> Self { Self::default() }
+
+            /// Creates a list from `bytes`, repairing a malformed list.
+            ///
+            /// The list ends at the first empty string. If `bytes` is not
+            /// terminated, the missing null characters are appended.
+            pub fn from_bytes_lossy(bytes: &[$t]) -> Self {
+                let mut v = Vec::with_capacity(bytes.len() + 2);
+                for s in lossy_strs(bytes) {
+                    v.extend_from_slice(s);
+                    v.push(0);
+                }
+                v.push(0);
+                Self { inner: v }
+            }
+
+            /// Appends `s` to the end of the list.
+            ///
+            /// If `s` is empty or contains a null character, this function
+            /// returns an error.
+            pub fn push(&mut self, s: &$s) -> Result<(), MultiStrError> {
+                self.push_bytes(s.to_bytes())
+            }
+
+            fn push_bytes(&mut self, s: &[$t]) -> Result<(), MultiStrError> {
+                if s.is_empty() {
+                    return Err(MultiStrError::EmptyString {
+                        index: self.iter().count(),
+                    });
+                }
+                if s.contains(&0) {
+                    return Err(MultiStrError::InteriorNul {
+                        index: self.iter().count(),
+                    });
+                }
+                self.inner.pop();
+                self.inner.extend_from_slice(s);
+                self.inner.extend_from_slice(&[0, 0]);
+                Ok(())
+            }
+
+            /// Returns the list as a boxed slice.
+            #[inline]
+            pub fn into_boxed_bytes_with_nul(self) -> Box<[$t]> {
+                self.inner.into_boxed_slice()
+            }
+
+            #[doc = concat!("Returns the list as `&`[`", stringify!($ms), "`].")]
+            #[inline]
+            pub fn as_multi_str(&self) -> &$ms {
+                unsafe { $ms::from_bytes_with_nul_unchecked(&self.inner) }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl Default for $mo {
+            #[inline]
+            fn default() -> Self { Self { inner: vec![0] } }
+        }
+
+        #[cfg(feature = "std")]
+        impl ops::Deref for $mo {
+            type Target = $ms;
+
+            #[inline]
+            fn deref(&self) -> &$ms { self.as_multi_str() }
+        }
+
+        #[cfg(feature = "std")]
+        impl Borrow<$ms> for $mo {
+            #[inline]
+            fn borrow(&self) -> &$ms { self }
+        }
+
+        #[cfg(feature = "std")]
+        impl ToOwned for $ms {
+            type Owned = $mo;
+
+            #[inline]
+            fn to_owned(&self) -> $mo { $mo::from(self) }
+        }
+
+        #[cfg(feature = "std")]
+        impl From<&$ms> for $mo {
+            #[inline]
+            fn from(x: &$ms) -> Self {
+                Self {
+                    inner: x.as_bytes_with_nul().to_vec(),
+                }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl fmt::Debug for $mo {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(self.as_multi_str(), f)
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl<'a> IntoIterator for &'a $mo {
+            type IntoIter = $it<'a>;
+            type Item = &'a $s;
+
+            #[inline]
+            fn into_iter(self) -> $it<'a> { self.iter() }
+        }
+
+        #[cfg(feature = "std")]
+        impl<'a> TryFrom<&'a [&'a $s]> for $mo {
+            type Error = MultiStrError;
+
+            fn try_from(x: &'a [&'a $s]) -> Result<Self, Self::Error> {
+                let mut ret = Self::new();
+                for s in x {
+                    ret.push(s)?;
+                }
+                Ok(ret)
+            }
+        }
+    };
+}
+
+multi_str_impl!(WMultiStr, WMultiString, WMultiStrIter, WStr, u16);
+multi_str_impl!(AMultiStr, AMultiString, AMultiStrIter, AStr, u8);
+
+#[cfg(feature = "std")]
+impl WMultiString {
+    /// Creates a list from UTF-8 strings.
+    ///
+    /// If a string is empty or contains a null character, this function
+    /// returns an error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WMultiString;
+    /// let s = WMultiString::from_strs(["a", "bc"]).unwrap();
+    /// let v: Vec<String> = s.iter().map(|x| x.to_string_lossy()).collect();
+    /// assert_eq!(v, ["a", "bc"]);
+    /// assert_eq!(s.as_bytes_with_nul(), [0x61, 0, 0x62, 0x63, 0, 0]);
+    /// ```
+    pub fn from_strs<I, S>(iter: I) -> Result<Self, MultiStrError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut ret = Self::new();
+        for s in iter {
+            let v: Vec<u16> = s.as_ref().encode_utf16().collect();
+            ret.push_bytes(&v)?;
+        }
+        Ok(ret)
+    }
+
+    /// Creates a list from [`WStr`]s.
+    ///
+    /// If a string is empty or contains a null character, this function
+    /// returns an error.
+    pub fn from_wstrs<'a, I: IntoIterator<Item = &'a WStr>>(
+        iter: I,
+    ) -> Result<Self, MultiStrError> {
+        let mut ret = Self::new();
+        for s in iter {
+            ret.push(s)?;
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AMultiString {
+    /// Creates a list from [`AStr`]s.
+    ///
+    /// If a string is empty or contains a null character, this function
+    /// returns an error.
+    pub fn from_astrs<'a, I: IntoIterator<Item = &'a AStr>>(
+        iter: I,
+    ) -> Result<Self, MultiStrError> {
+        let mut ret = Self::new();
+        for s in iter {
+            ret.push(s)?;
+        }
+        Ok(ret)
+    }
+}
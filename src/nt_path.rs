@@ -0,0 +1,418 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Conversions between Win32 paths and NT paths.
+//!
+//! Win32 APIs convert a path such as `C:\foo` into an NT path such as
+//! `\??\C:\foo` by `RtlDosPathNameToNtPathName_U`, which normalizes the
+//! path and is limited to `MAX_PATH` characters unless the long path
+//! support is enabled. A verbatim path such as `\\?\C:\foo` is passed to
+//! the object manager without the normalization and the limit.
+//!
+//! [`WStr::to_nt_path`] emulates `RtlDosPathNameToNtPathName_U` on any host,
+//! and [`WStr::to_verbatim_path`] converts a path into the verbatim path that
+//! refers to the same file.
+use crate::{__lib::ops, *};
+
+/// An error indicating that a path could not be converted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NtPathError {
+    /// The path is relative to the current directory or the current
+    /// directory of a drive, such as `foo`, `\foo` and `C:foo`.
+    RelativePath,
+    /// The path is not an absolute NT path.
+    NotNtPath,
+}
+
+impl fmt::Display for NtPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RelativePath => {
+                write!(f, "path is relative to the current directory")
+            }
+            Self::NotNtPath => write!(f, "path is not an absolute NT path"),
+        }
+    }
+}
+
+impl std::error::Error for NtPathError {}
+
+const BACKSLASH: u16 = b'\\' as u16;
+const SLASH: u16 = b'/' as u16;
+const DOT: u16 = b'.' as u16;
+const COLON: u16 = b':' as u16;
+const SPACE: u16 = b' ' as u16;
+const QUESTION: u16 = b'?' as u16;
+
+#[inline]
+fn is_sep(x: u16) -> bool { x == BACKSLASH || x == SLASH }
+
+/// Returns `true` if `s` starts with `pat`, ignoring ASCII case.
+fn starts_with_ignore_case(s: &[u16], pat: &str) -> bool {
+    s.len() >= pat.len()
+        && pat
+            .bytes()
+            .zip(s)
+            .all(|(a, &b)| b < 0x80 && (b as u8).eq_ignore_ascii_case(&a))
+}
+
+/// Returns `true` if `s` starts with `pat`.
+fn starts_with(s: &[u16], pat: &str) -> bool {
+    s.len() >= pat.len() && pat.bytes().zip(s).all(|(a, &b)| a as u16 == b)
+}
+
+/// The type of a Win32 path, as `RtlDetermineDosPathNameType_U` returns.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum PathType {
+    /// `\\server\share`.
+    UncAbsolute,
+    /// `C:\foo`.
+    DriveAbsolute,
+    /// `C:foo`.
+    DriveRelative,
+    /// `\foo`.
+    Rooted,
+    /// `foo`.
+    Relative,
+    /// `\\.\foo` and `\\?\foo`.
+    LocalDevice,
+    /// `\\.` and `\\?`.
+    RootLocalDevice,
+}
+
+fn path_type(s: &[u16]) -> PathType {
+    let at = |i: usize| s.get(i).copied().unwrap_or(0);
+    if is_sep(at(0)) {
+        if !is_sep(at(1)) {
+            PathType::Rooted
+        } else if at(2) != DOT && at(2) != QUESTION {
+            PathType::UncAbsolute
+        } else if is_sep(at(3)) {
+            PathType::LocalDevice
+        } else if s.len() > 3 {
+            PathType::UncAbsolute
+        } else {
+            PathType::RootLocalDevice
+        }
+    } else if s.is_empty() || at(1) != COLON {
+        PathType::Relative
+    } else if is_sep(at(2)) {
+        PathType::DriveAbsolute
+    } else {
+        PathType::DriveRelative
+    }
+}
+
+/// Returns the range of the DOS device name in `s`, such as `CON` in
+/// `C:\foo\con.txt`, as `RtlIsDosDeviceName_U` does.
+fn dos_device_name(s: &[u16]) -> Option<ops::Range<usize>> {
+    match path_type(s) {
+        PathType::UncAbsolute
+        | PathType::LocalDevice
+        | PathType::RootLocalDevice => return None,
+        _ => {}
+    }
+    // Trailing colons are ignored.
+    let mut end = s.len();
+    while end > 0 && s[end - 1] == COLON {
+        end -= 1;
+    }
+    let mut start = end;
+    while start > 0 && !is_sep(s[start - 1]) && !(start == 2 && s[1] == COLON) {
+        start -= 1;
+    }
+    // The extension is ignored.
+    if let Some(i) = s[start..].iter().position(|&x| x == DOT) {
+        end = start + i;
+        if end > start && s[end - 1] == COLON {
+            end -= 1;
+        }
+    }
+    while end > start && s[end - 1] == SPACE {
+        end -= 1;
+    }
+    let name = &s[start..end];
+    let is_device = match name.len() {
+        3 => ["AUX", "CON", "NUL", "PRN"]
+            .iter()
+            .any(|x| starts_with_ignore_case(name, x)),
+        4 => {
+            (starts_with_ignore_case(name, "COM")
+                || starts_with_ignore_case(name, "LPT"))
+                && (b'1' as u16..=b'9' as u16).contains(&name[3])
+        }
+        _ => false,
+    };
+    is_device.then_some(start..end)
+}
+
+/// Returns the length of the UNC prefix such as `\\server\share\`.
+fn unc_prefix_len(s: &[u16]) -> usize {
+    let mut i = 2;
+    for _ in 0..2 {
+        while i < s.len() && !is_sep(s[i]) {
+            i += 1;
+        }
+        while i < s.len() && is_sep(s[i]) {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// Normalizes the path after `mark`, which is the length of its root.
+///
+/// This follows `RtlGetFullPathName_U`:
+///
+/// - `/` is converted into `\`, and consecutive `\`s are collapsed.
+/// - `.` is removed, and `..` removes the previous component.
+/// - A trailing dot is removed from each directory name.
+/// - Trailing dots and spaces are removed from the path.
+fn collapse(v: &mut Vec<u16>, mark: usize) {
+    for x in v.iter_mut() {
+        if *x == SLASH {
+            *x = BACKSLASH;
+        }
+    }
+    let start = mark.max(1);
+    let mut w = start;
+    for r in start..v.len() {
+        if v[r] != BACKSLASH || v[w - 1] != BACKSLASH {
+            v[w] = v[r];
+            w += 1;
+        }
+    }
+    v.truncate(w);
+
+    // Moves `p` to the start of the previous component.
+    let back = |v: &[u16], mut p: usize| {
+        if p > mark {
+            p -= 1;
+            while p > mark && v[p - 1] != BACKSLASH {
+                p -= 1;
+            }
+        }
+        p
+    };
+    let mut p = mark;
+    while p < v.len() {
+        if v[p] == DOT {
+            match (v.get(p + 1), v.get(p + 2)) {
+                (Some(&BACKSLASH), _) => {
+                    v.drain(p..p + 2);
+                    continue;
+                }
+                (None, _) => {
+                    if p > mark {
+                        p -= 1;
+                    }
+                    v.truncate(p);
+                    continue;
+                }
+                (Some(&DOT), Some(&BACKSLASH)) => {
+                    let next = p + 3;
+                    p = back(v, p);
+                    v.drain(p..next);
+                    continue;
+                }
+                (Some(&DOT), None) => {
+                    p = back(v, p);
+                    if p > mark {
+                        p -= 1;
+                    }
+                    v.truncate(p);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        while p < v.len() && v[p] != BACKSLASH {
+            p += 1;
+        }
+        if p < v.len() {
+            if p > mark && v[p - 1] == DOT {
+                v.remove(p - 1);
+            } else {
+                p += 1;
+            }
+        }
+    }
+    while p > mark && (v[p - 1] == SPACE || v[p - 1] == DOT) {
+        p -= 1;
+    }
+    v.truncate(p);
+}
+
+/// Returns the full path of `s` as `RtlGetFullPathName_U` does.
+fn full_path_name(s: &[u16]) -> Result<Vec<u16>, NtPathError> {
+    if let Some(r) = dos_device_name(s) {
+        let mut v = r"\\.\".encode_utf16().collect::<Vec<_>>();
+        v.extend_from_slice(&s[r]);
+        return Ok(v);
+    }
+    let mark = match path_type(s) {
+        PathType::UncAbsolute => unc_prefix_len(s),
+        PathType::LocalDevice => 4,
+        PathType::RootLocalDevice => {
+            return Ok(r"\\.\".encode_utf16().collect());
+        }
+        PathType::DriveAbsolute => 3,
+        PathType::DriveRelative | PathType::Rooted | PathType::Relative => {
+            return Err(NtPathError::RelativePath);
+        }
+    };
+    let mut v = s.to_vec();
+    collapse(&mut v, mark);
+    Ok(v)
+}
+
+/// Converts a Win32 path into an NT path, and returns the NT path without
+/// `\??\`.
+fn to_nt_path_body(s: &[u16]) -> Result<Vec<u16>, NtPathError> {
+    if starts_with(s, r"\\?\") || starts_with(s, r"\??\") {
+        return Ok(s[4..].to_vec());
+    }
+    let v = full_path_name(s)?;
+    Ok(match path_type(&v) {
+        PathType::UncAbsolute => {
+            let mut ret = "UNC\\".encode_utf16().collect::<Vec<_>>();
+            ret.extend_from_slice(&v[2..]);
+            ret
+        }
+        PathType::LocalDevice => v[4..].to_vec(),
+        _ => v,
+    })
+}
+
+/// Prepends `prefix` to `s`.
+fn with_prefix(prefix: &str, s: &[u16]) -> WString {
+    let mut v = prefix.encode_utf16().collect::<Vec<_>>();
+    v.extend_from_slice(s);
+    unsafe { WString::new_unchecked(v) }
+}
+
+impl WStr {
+    /// Converts a Win32 path into an NT path like
+    /// `RtlDosPathNameToNtPathName_U`.
+    ///
+    /// - A verbatim path such as `\\?\C:\foo` is converted into
+    ///   `\??\C:\foo` without the normalization, and an NT path such as
+    ///   `\??\C:\foo` is returned as it is.
+    /// - A path whose last component is a DOS device name, such as `CON`,
+    ///   `C:\foo\nul.txt` and `COM1:`, is converted into `\??\CON`, `\??\nul`
+    ///   and `\??\COM1`.
+    /// - Otherwise, the path is normalized: `/` is converted into `\`, `.`
+    ///   and `..` are resolved, and trailing dots and spaces are removed
+    ///   from the path. `C:\foo` is converted into `\??\C:\foo`,
+    ///   `\\server\share` into `\??\UNC\server\share`, and `\\.\COM1` into
+    ///   `\??\COM1`.
+    ///
+    /// Since the current directory is not available, this function returns
+    /// [`NtPathError::RelativePath`] for relative paths such as `foo`,
+    /// `\foo` and `C:foo`.
+    ///
+    /// DOS device names follow Windows 10 and Wine. Windows 11 recognizes
+    /// DOS device names in fewer paths.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    ///
+    /// let s = wstr!(r"C:\Windows\.\Temp\..\System32. ")
+    ///     .to_nt_path()
+    ///     .unwrap();
+    /// assert_eq!(s.to_string_lossy(), r"\??\C:\Windows\System32");
+    /// let s = wstr!(r"C:\Temp\con.txt").to_nt_path().unwrap();
+    /// assert_eq!(s.to_string_lossy(), r"\??\con");
+    /// ```
+    pub fn to_nt_path(&self) -> Result<WString, NtPathError> {
+        let v = to_nt_path_body(self.to_bytes())?;
+        Ok(with_prefix(r"\??\", &v))
+    }
+
+    /// Converts a Win32 path into a verbatim path, which is not limited to
+    /// `MAX_PATH` characters.
+    ///
+    /// The verbatim path refers to the same file as `self`: it is
+    /// [`WStr::to_nt_path`] with `\\?\` instead of `\??\`. For example,
+    /// `C:\foo\..\bar` is converted into `\\?\C:\bar`, and
+    /// `\\server\share\foo` into `\\?\UNC\server\share\foo`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    ///
+    /// let s = wstr!(r"//server/share/foo.").to_verbatim_path().unwrap();
+    /// assert_eq!(s.to_string_lossy(), r"\\?\UNC\server\share\foo");
+    /// ```
+    pub fn to_verbatim_path(&self) -> Result<WString, NtPathError> {
+        let v = to_nt_path_body(self.to_bytes())?;
+        Ok(with_prefix(r"\\?\", &v))
+    }
+
+    /// Converts an NT path into a Win32 path.
+    ///
+    /// `\??\`, `\DosDevices\` and `\GLOBAL??\` are the directory of DOS
+    /// devices, and the following paths are converted:
+    ///
+    /// - `\??\C:\foo` into `C:\foo`.
+    /// - `\??\UNC\server\share` and `\Device\Mup\server\share` into
+    ///   `\\server\share`.
+    /// - `\??\COM1` into `\\.\COM1`, and `\Device\Null` into `\\.\NUL`.
+    ///
+    /// If the Win32 path would be normalized into another path, such as
+    /// `\??\C:\foo.`, the verbatim path `\\?\C:\foo.` is returned. Other NT
+    /// paths such as `\Device\HarddiskVolume1\foo` are converted into
+    /// `\\?\GLOBALROOT\Device\HarddiskVolume1\foo`.
+    ///
+    /// If `self` does not start with `\`, this function returns
+    /// [`NtPathError::NotNtPath`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    ///
+    /// let s = wstr!(r"\??\UNC\server\share").nt_to_win32_path().unwrap();
+    /// assert_eq!(s.to_string_lossy(), r"\\server\share");
+    /// ```
+    pub fn nt_to_win32_path(&self) -> Result<WString, NtPathError> {
+        let s = self.to_bytes();
+        let mut body = None;
+        for prefix in [r"\??\", r"\DosDevices\", r"\GLOBAL??\"] {
+            if starts_with_ignore_case(s, prefix) {
+                body = Some(s[prefix.len()..].to_vec());
+                break;
+            }
+        }
+        if starts_with_ignore_case(s, r"\Device\Mup\") {
+            let mut v = "UNC\\".encode_utf16().collect::<Vec<_>>();
+            v.extend_from_slice(&s[12..]);
+            body = Some(v);
+        } else if s.len() == 12 && starts_with_ignore_case(s, r"\Device\Null") {
+            body = Some("NUL".encode_utf16().collect());
+        }
+        let Some(body) = body else {
+            if s.first() != Some(&BACKSLASH) {
+                return Err(NtPathError::NotNtPath);
+            }
+            return Ok(with_prefix(r"\\?\GLOBALROOT", s));
+        };
+        let ret = if starts_with_ignore_case(&body, "UNC\\") {
+            with_prefix(r"\\", &body[4..])
+        } else if path_type(&body) == PathType::DriveAbsolute
+            && body[2] == BACKSLASH
+        {
+            with_prefix("", &body)
+        } else {
+            with_prefix(r"\\.\", &body)
+        };
+        // The Win32 path must be converted back into the same NT path.
+        if to_nt_path_body(ret.to_bytes()).as_deref() == Ok(&body[..]) {
+            Ok(ret)
+        } else {
+            Ok(with_prefix(r"\\?\", &body))
+        }
+    }
+}
@@ -1,14 +1,22 @@
 // Copyright takubokudori.
 // This source code is licensed under the MIT or Apache-2.0 license.
+#[cfg(windows)]
 use crate::{
     __lib::ptr::null_mut,
+    raw::{RtlInitAnsiString, RtlInitUnicodeString},
+};
+use crate::{
     AStr, WStr,
-    raw::{
-        ANSI_STRING, RtlInitAnsiString, RtlInitUnicodeString, UNICODE_STRING,
-    },
+    raw::{ANSI_STRING, PSTR, PWSTR, UNICODE_STRING, USHORT},
 };
 use core::ops;
 
+/// The maximum `Length` of `UNICODE_STRING` in bytes.
+const UNICODE_STRING_MAX_LENGTH: usize = 0xFFFC;
+
+/// The maximum `Length` of `ANSI_STRING` in bytes.
+const ANSI_STRING_MAX_LENGTH: usize = 0xFFFE;
+
 /// Represents [UNICODE_STRING](https://docs.microsoft.com/en-us/windows/win32/api/ntdef/ns-ntdef-_unicode_string).
 #[derive(Debug, Clone)]
 pub struct UnicodeString<'a> {
@@ -18,6 +26,9 @@ pub struct UnicodeString<'a> {
 
 impl<'a> UnicodeString<'a> {
     /// Creates UnicodeString.
+    ///
+    /// On non-Windows hosts, `RtlInitUnicodeString` is emulated.
+    #[cfg(windows)]
     pub fn new(s: &'a WStr) -> Self {
         let mut us = UNICODE_STRING {
             Length: 0,
@@ -30,6 +41,58 @@ impl<'a> UnicodeString<'a> {
         Self { us, s }
     }
 
+    /// Creates UnicodeString.
+    ///
+    /// On non-Windows hosts, `RtlInitUnicodeString` is emulated. A string
+    /// longer than [`UNICODE_STRING`] can represent is truncated like
+    /// `RtlInitUnicodeString`.
+    #[cfg(not(windows))]
+    pub fn new(s: &'a WStr) -> Self { Self::init(s, true) }
+
+    /// Creates UnicodeString without calling `RtlInitUnicodeString`.
+    ///
+    /// `Length` is computed up to the first null character, so this function
+    /// can be used to define statics like `DECLARE_CONST_UNICODE_STRING`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is longer than [`UNICODE_STRING`] can represent.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{UnicodeString, wstr};
+    ///
+    /// static NAME: UnicodeString<'static> =
+    ///     UnicodeString::from_static(wstr!("\\Device\\Null"));
+    /// assert_eq!(NAME.as_raw().Length, 24);
+    /// ```
+    pub const fn from_static(s: &'static WStr) -> UnicodeString<'static> {
+        UnicodeString::init(s, false)
+    }
+
+    /// Computes `Length` up to the first null character.
+    ///
+    /// If the string is too long, `Length` is capped at the maximum if
+    /// `truncate` is `true`, and this function panics otherwise.
+    const fn init(s: &'a WStr, truncate: bool) -> Self {
+        let bytes = s.to_bytes_with_nul();
+        let mut len = 0;
+        while len < bytes.len() && bytes[len] != 0 {
+            len += 1;
+        }
+        if len * 2 > UNICODE_STRING_MAX_LENGTH {
+            assert!(truncate, "the string is too long for UNICODE_STRING");
+            len = UNICODE_STRING_MAX_LENGTH / 2;
+        }
+        let us = UNICODE_STRING {
+            Length: (len * 2) as USHORT,
+            MaximumLength: (len * 2 + 2) as USHORT,
+            Buffer: s.as_ptr() as PWSTR,
+        };
+        Self { us, s }
+    }
+
     /// Returns &[`UNICODE_STRING`].
     pub fn as_raw(&self) -> &UNICODE_STRING { &self.us }
 
@@ -52,6 +115,10 @@ impl PartialEq for UnicodeString<'_> {
 
 impl Eq for UnicodeString<'_> {}
 
+// `UnicodeString` only exposes the buffer of a shared `WStr` through `&self`.
+unsafe impl Send for UnicodeString<'_> {}
+unsafe impl Sync for UnicodeString<'_> {}
+
 /// Represents [ANSI_STRING](https://docs.microsoft.com/en-us/windows/win32/api/ntdef/ns-ntdef-string).
 #[derive(Debug, Clone)]
 pub struct AnsiString<'a> {
@@ -61,6 +128,9 @@ pub struct AnsiString<'a> {
 
 impl<'a> AnsiString<'a> {
     /// Creates AnsiString.
+    ///
+    /// On non-Windows hosts, `RtlInitAnsiString` is emulated.
+    #[cfg(windows)]
     pub fn new(s: &'a AStr) -> Self {
         let mut us = ANSI_STRING {
             Length: 0,
@@ -73,6 +143,48 @@ impl<'a> AnsiString<'a> {
         Self { us, s }
     }
 
+    /// Creates AnsiString.
+    ///
+    /// On non-Windows hosts, `RtlInitAnsiString` is emulated. A string
+    /// longer than [`ANSI_STRING`] can represent is truncated like
+    /// `RtlInitAnsiString`.
+    #[cfg(not(windows))]
+    pub fn new(s: &'a AStr) -> Self { Self::init(s, true) }
+
+    /// Creates AnsiString without calling `RtlInitAnsiString`.
+    ///
+    /// `Length` is computed up to the first null character, so this function
+    /// can be used to define statics like `DECLARE_CONST_ANSI_STRING`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is longer than [`ANSI_STRING`] can represent.
+    pub const fn from_static(s: &'static AStr) -> AnsiString<'static> {
+        AnsiString::init(s, false)
+    }
+
+    /// Computes `Length` up to the first null character.
+    ///
+    /// If the string is too long, `Length` is capped at the maximum if
+    /// `truncate` is `true`, and this function panics otherwise.
+    const fn init(s: &'a AStr, truncate: bool) -> Self {
+        let bytes = s.to_bytes_with_nul();
+        let mut len = 0;
+        while len < bytes.len() && bytes[len] != 0 {
+            len += 1;
+        }
+        if len > ANSI_STRING_MAX_LENGTH {
+            assert!(truncate, "the string is too long for ANSI_STRING");
+            len = ANSI_STRING_MAX_LENGTH;
+        }
+        let us = ANSI_STRING {
+            Length: len as USHORT,
+            MaximumLength: (len + 1) as USHORT,
+            Buffer: s.as_ptr() as PSTR,
+        };
+        Self { us, s }
+    }
+
     /// Returns &[`ANSI_STRING`].
     pub fn as_raw(&self) -> &ANSI_STRING { &self.us }
 
@@ -94,3 +206,7 @@ impl PartialEq for AnsiString<'_> {
 }
 
 impl Eq for AnsiString<'_> {}
+
+// `AnsiString` only exposes the buffer of a shared `AStr` through `&self`.
+unsafe impl Send for AnsiString<'_> {}
+unsafe impl Sync for AnsiString<'_> {}
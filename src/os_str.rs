@@ -0,0 +1,164 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Conversions between [`WStr`] and [`OsStr`].
+//!
+//! On Windows, [`OsStr`] is converted with `OsStrExt::encode_wide` and
+//! `OsStringExt::from_wide` without loss.
+//! On non-Windows hosts, [`OsStr`] is treated as WTF-8 bytes. Unpaired
+//! surrogates of [`WStr`] are stored as WTF-8 on Unix, and replaced with
+//! `\u{FFFD}` on the other hosts.
+use crate::*;
+use std::{
+    convert::TryFrom,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+/// An error indicating that [`OsStr`] could not be converted to [`WString`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FromOsStrError {
+    /// The string has a null character at `position` in code units.
+    InteriorNul { position: usize },
+    /// The string is not valid WTF-8. This error is returned on non-Windows
+    /// hosts only.
+    InvalidWtf8 { valid_up_to: usize },
+}
+
+impl fmt::Display for FromOsStrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InteriorNul { position } => {
+                write!(f, "nul byte found at position: {}", position)
+            }
+            Self::InvalidWtf8 { valid_up_to } => write!(
+                f,
+                "invalid WTF-8 sequence found at position: {}",
+                valid_up_to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FromOsStrError {}
+
+impl From<NulError<u16>> for FromOsStrError {
+    #[inline]
+    fn from(x: NulError<u16>) -> Self {
+        Self::InteriorNul {
+            position: x.nul_position(),
+        }
+    }
+}
+
+impl From<Wtf8Error> for FromOsStrError {
+    #[inline]
+    fn from(x: Wtf8Error) -> Self {
+        Self::InvalidWtf8 {
+            valid_up_to: x.valid_up_to(),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn os_str_to_wide(s: &OsStr) -> Result<Vec<u16>, FromOsStrError> {
+    use std::os::windows::ffi::OsStrExt;
+    Ok(s.encode_wide().collect())
+}
+
+#[cfg(not(windows))]
+fn os_str_to_wide(s: &OsStr) -> Result<Vec<u16>, FromOsStrError> {
+    Ok(Wtf8::from_bytes(s.as_encoded_bytes())?
+        .encode_wide()
+        .collect())
+}
+
+#[cfg(windows)]
+fn wide_to_os_string(v: &[u16]) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    OsString::from_wide(v)
+}
+
+#[cfg(unix)]
+fn wide_to_os_string(v: &[u16]) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(Wtf8Buf::from_wide(v).into_bytes())
+}
+
+#[cfg(not(any(windows, unix)))]
+fn wide_to_os_string(v: &[u16]) -> OsString {
+    OsString::from(String::from_utf16_lossy(v))
+}
+
+impl WString {
+    /// Converts `s` to [`WString`].
+    ///
+    /// If `s` contains a null character, this function returns
+    /// [`FromOsStrError::InteriorNul`].
+    /// On non-Windows hosts, `s` must be valid WTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use windy::WString;
+    /// let s = WString::from_os_str(Path::new("C:\\Windows")).unwrap();
+    /// assert_eq!(s.to_string_lossy(), "C:\\Windows");
+    /// ```
+    pub fn from_os_str<S: AsRef<OsStr>>(s: S) -> Result<Self, FromOsStrError> {
+        Ok(Self::new(os_str_to_wide(s.as_ref())?)?)
+    }
+}
+
+impl WStr {
+    /// Converts [`WStr`] to [`OsString`].
+    #[inline]
+    pub fn to_os_string(&self) -> OsString {
+        wide_to_os_string(self.to_bytes())
+    }
+
+    /// Converts [`WStr`] to [`PathBuf`].
+    #[inline]
+    pub fn to_path_buf(&self) -> PathBuf { PathBuf::from(self.to_os_string()) }
+}
+
+impl TryFrom<&OsStr> for WString {
+    type Error = FromOsStrError;
+
+    #[inline]
+    fn try_from(x: &OsStr) -> Result<Self, Self::Error> { Self::from_os_str(x) }
+}
+
+impl TryFrom<OsString> for WString {
+    type Error = FromOsStrError;
+
+    #[inline]
+    fn try_from(x: OsString) -> Result<Self, Self::Error> {
+        Self::from_os_str(x)
+    }
+}
+
+impl TryFrom<&Path> for WString {
+    type Error = FromOsStrError;
+
+    #[inline]
+    fn try_from(x: &Path) -> Result<Self, Self::Error> { Self::from_os_str(x) }
+}
+
+impl TryFrom<PathBuf> for WString {
+    type Error = FromOsStrError;
+
+    #[inline]
+    fn try_from(x: PathBuf) -> Result<Self, Self::Error> {
+        Self::from_os_str(x)
+    }
+}
+
+impl From<&WStr> for OsString {
+    #[inline]
+    fn from(x: &WStr) -> Self { x.to_os_string() }
+}
+
+impl From<&WStr> for PathBuf {
+    #[inline]
+    fn from(x: &WStr) -> Self { x.to_path_buf() }
+}
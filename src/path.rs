@@ -0,0 +1,741 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Windows paths.
+//!
+//! [`WPath`] and [`WPathBuf`] handle paths with the Windows semantics on
+//! any host, unlike [`std::path`] which follows the semantics of the host.
+//! Both `\` and `/` are separators except in verbatim (`\\?\`) and NT
+//! (`\??\`) paths, where only `\` is a separator.
+use crate::{
+    __lib::{
+        hash::{Hash, Hasher},
+        ops,
+    },
+    *,
+};
+use std::borrow::Borrow;
+
+const BACKSLASH: u16 = b'\\' as u16;
+const SLASH: u16 = b'/' as u16;
+const COLON: u16 = b':' as u16;
+const DOT: u16 = b'.' as u16;
+const QUESTION: u16 = b'?' as u16;
+
+#[inline]
+fn is_sep(x: u16, verbatim: bool) -> bool {
+    x == BACKSLASH || (!verbatim && x == SLASH)
+}
+
+/// A prefix of a path.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WPrefix<'a> {
+    /// A verbatim prefix such as `\\?\foo`.
+    Verbatim(&'a WPath),
+    /// A verbatim UNC prefix such as `\\?\UNC\server\share`.
+    VerbatimUNC(&'a WPath, &'a WPath),
+    /// A verbatim disk prefix such as `\\?\C:`.
+    VerbatimDisk(u8),
+    /// A device namespace prefix such as `\\.\COM1`.
+    ///
+    /// `?` instead of `.` also makes a device namespace prefix unless the
+    /// prefix is exactly `\\?\`, such as `//?/C:`.
+    DeviceNS(&'a WPath),
+    /// A UNC prefix such as `\\server\share`.
+    UNC(&'a WPath, &'a WPath),
+    /// A disk prefix such as `C:`.
+    Disk(u8),
+    /// An NT object manager prefix such as `\??\C:`.
+    Nt(&'a WPath),
+}
+
+impl WPrefix<'_> {
+    /// Returns `true` if the prefix disables the normalization of the path,
+    /// that is, it is a verbatim or NT prefix.
+    #[inline]
+    pub fn is_verbatim(&self) -> bool {
+        matches!(
+            self,
+            Self::Verbatim(_)
+                | Self::VerbatimUNC(..)
+                | Self::VerbatimDisk(_)
+                | Self::Nt(_)
+        )
+    }
+
+    /// Returns `true` if the prefix is followed by the root implicitly.
+    #[inline]
+    fn has_implicit_root(&self) -> bool { !matches!(self, Self::Disk(_)) }
+}
+
+/// Returns the length of `s` up to the first separator.
+#[inline]
+fn until_sep(s: &[u16], verbatim: bool) -> usize {
+    s.iter()
+        .position(|&x| is_sep(x, verbatim))
+        .unwrap_or(s.len())
+}
+
+/// Parses the prefix of `s` and returns it and its length.
+fn parse_prefix(s: &[u16]) -> Option<(WPrefix<'_>, usize)> {
+    let p = |s| unsafe { WPath::from_wide_unchecked(s) };
+    let is_disk = |s: &[u16]| {
+        s.len() >= 2
+            && s[1] == COLON
+            && s[0] < 0x80
+            && (s[0] as u8).is_ascii_alphabetic()
+    };
+    let starts = |pat: &str| {
+        s.len() >= pat.len() && pat.bytes().zip(s).all(|(a, &b)| a as u16 == b)
+    };
+    if starts(r"\\?\") {
+        let rest = &s[4..];
+        if rest.len() >= 4
+            && rest[..3].iter().map(|&x| x as u8).eq(*b"UNC")
+            && rest[3] == BACKSLASH
+        {
+            let rest = &rest[4..];
+            let server = until_sep(rest, true);
+            let share_start = (server + 1).min(rest.len());
+            let share = until_sep(&rest[share_start..], true);
+            return Some((
+                WPrefix::VerbatimUNC(
+                    p(&rest[..server]),
+                    p(&rest[share_start..share_start + share]),
+                ),
+                8 + share_start + share,
+            ));
+        }
+        if is_disk(rest) && (rest.len() == 2 || rest[2] == BACKSLASH) {
+            return Some((WPrefix::VerbatimDisk(rest[0] as u8), 6));
+        }
+        let n = until_sep(rest, true);
+        return Some((WPrefix::Verbatim(p(&rest[..n])), 4 + n));
+    }
+    if starts(r"\??\") {
+        let n = until_sep(&s[4..], true);
+        return Some((WPrefix::Nt(p(&s[4..4 + n])), 4 + n));
+    }
+    if s.len() >= 2 && is_sep(s[0], false) && is_sep(s[1], false) {
+        let rest = &s[2..];
+        if rest.len() >= 2
+            && (rest[0] == DOT || rest[0] == QUESTION)
+            && is_sep(rest[1], false)
+        {
+            let n = until_sep(&rest[2..], false);
+            return Some((WPrefix::DeviceNS(p(&rest[2..2 + n])), 4 + n));
+        }
+        let server = until_sep(rest, false);
+        let share_start = (server + 1).min(rest.len());
+        let share = until_sep(&rest[share_start..], false);
+        return Some((
+            WPrefix::UNC(
+                p(&rest[..server]),
+                p(&rest[share_start..share_start + share]),
+            ),
+            2 + share_start + share,
+        ));
+    }
+    if is_disk(s) {
+        return Some((WPrefix::Disk(s[0] as u8), 2));
+    }
+    None
+}
+
+/// A component of a path.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WComponent<'a> {
+    /// A prefix such as `C:` or `\\server\share`.
+    Prefix(WPrefix<'a>),
+    /// The root directory.
+    RootDir,
+    /// `.`.
+    CurDir,
+    /// `..`.
+    ParentDir,
+    /// A normal component such as `a` of `C:\a`.
+    Normal(&'a WPath),
+}
+
+/// An iterator over the components of a path.
+///
+/// This struct is created by [`WPath::components`].
+#[derive(Clone, Debug)]
+pub struct WComponents<'a> {
+    prefix: Option<WPrefix<'a>>,
+    root: bool,
+    verbatim: bool,
+    /// `true` if `.` at the beginning of `body` is a component.
+    cur_dir: bool,
+    body: &'a [u16],
+    /// The offset of `body` in the path.
+    offset: usize,
+}
+
+impl<'a> WComponents<'a> {
+    fn new(path: &'a [u16]) -> Self {
+        let (prefix, n) = match parse_prefix(path) {
+            Some((x, n)) => (Some(x), n),
+            None => (None, 0),
+        };
+        let verbatim = prefix.is_some_and(|x| x.is_verbatim());
+        let body = &path[n..];
+        let root = prefix.is_some_and(|x| x.has_implicit_root())
+            || body.first().is_some_and(|&x| is_sep(x, verbatim));
+        Self {
+            prefix,
+            root,
+            verbatim,
+            cur_dir: prefix.is_none() && !root,
+            body,
+            offset: n,
+        }
+    }
+
+    /// Returns the next body component and its offset in the path.
+    fn next_body(&mut self) -> Option<(WComponent<'a>, usize)> {
+        loop {
+            let start =
+                self.body.iter().position(|&x| !is_sep(x, self.verbatim))?;
+            let body = &self.body[start..];
+            let n = until_sep(body, self.verbatim);
+            let (x, rest) = body.split_at(n);
+            let offset = self.offset + start;
+            let cur_dir = self.cur_dir && start == 0;
+            self.cur_dir = false;
+            self.offset += start + n;
+            self.body = rest;
+            let c = match x {
+                [DOT] if cur_dir || self.verbatim => WComponent::CurDir,
+                [DOT] => continue,
+                [DOT, DOT] => WComponent::ParentDir,
+                _ => {
+                    WComponent::Normal(unsafe { WPath::from_wide_unchecked(x) })
+                }
+            };
+            return Some((c, offset));
+        }
+    }
+}
+
+impl<'a> Iterator for WComponents<'a> {
+    type Item = WComponent<'a>;
+
+    fn next(&mut self) -> Option<WComponent<'a>> {
+        if let Some(x) = self.prefix.take() {
+            return Some(WComponent::Prefix(x));
+        }
+        if self.root {
+            self.root = false;
+            return Some(WComponent::RootDir);
+        }
+        self.next_body().map(|(x, _)| x)
+    }
+}
+
+/// A borrowed Windows path.
+///
+/// A path does not contain null characters.
+#[repr(transparent)]
+pub struct WPath {
+    inner: [u16],
+}
+
+impl WPath {
+    /// Converts `s` to [`WPath`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WPath, wstr};
+    ///
+    /// let p = WPath::new(wstr!(r"C:\Windows\System32"));
+    /// assert_eq!(p.parent(), Some(WPath::new(wstr!(r"C:\Windows"))));
+    /// ```
+    #[inline]
+    pub fn new<S: AsRef<WPath> + ?Sized>(s: &S) -> &WPath { s.as_ref() }
+
+    /// Converts `s` to [`WPath`] without any checks.
+    ///
+    /// # Safety
+    ///
+    /// `s` must not contain null characters.
+    #[inline]
+    pub unsafe fn from_wide_unchecked(s: &[u16]) -> &WPath {
+        unsafe { &*(s as *const [u16] as *const WPath) }
+    }
+
+    /// Returns the path as a slice without the terminating null character.
+    #[inline]
+    pub fn as_wide(&self) -> &[u16] { &self.inner }
+
+    /// Returns `true` if the path is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.inner.is_empty() }
+
+    /// Converts the path to [`WString`].
+    #[inline]
+    pub fn to_wstring(&self) -> WString {
+        unsafe { WString::new_unchecked(&self.inner) }
+    }
+
+    /// Converts the path to [`WPathBuf`].
+    #[inline]
+    pub fn to_wpath_buf(&self) -> WPathBuf { WPathBuf::from(self.to_wstring()) }
+
+    /// Converts the path to [`String`] lossily.
+    #[inline]
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.inner)
+    }
+
+    /// Returns an iterator over the components of the path.
+    ///
+    /// Separators are not yielded, and `.` is yielded only at the beginning
+    /// of a relative path or in a verbatim path.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WComponent, WPath, WPrefix, wstr};
+    ///
+    /// let p = WPath::new(wstr!(r"\\server\share\a/./b"));
+    /// let mut c = p.components();
+    /// assert!(matches!(
+    ///     c.next(),
+    ///     Some(WComponent::Prefix(WPrefix::UNC(..)))
+    /// ));
+    /// assert_eq!(c.next(), Some(WComponent::RootDir));
+    /// assert_eq!(c.next(), Some(WComponent::Normal(WPath::new(wstr!("a")))));
+    /// assert_eq!(c.next(), Some(WComponent::Normal(WPath::new(wstr!("b")))));
+    /// assert_eq!(c.next(), None);
+    /// ```
+    #[inline]
+    pub fn components(&self) -> WComponents<'_> {
+        WComponents::new(&self.inner)
+    }
+
+    /// Returns the prefix of the path.
+    #[inline]
+    pub fn prefix(&self) -> Option<WPrefix<'_>> {
+        parse_prefix(&self.inner).map(|(x, _)| x)
+    }
+
+    /// Returns `true` if the path has the root, such as `\` or
+    /// `\\server\share`.
+    #[inline]
+    pub fn has_root(&self) -> bool { WComponents::new(&self.inner).root }
+
+    /// Returns `true` if the path does not depend on the current directory.
+    ///
+    /// `C:a` and `\a` are relative since they depend on the current
+    /// directory of the drive and the current drive respectively.
+    pub fn is_absolute(&self) -> bool {
+        match self.prefix() {
+            Some(WPrefix::Disk(_)) => self.has_root(),
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the path is not absolute.
+    #[inline]
+    pub fn is_relative(&self) -> bool { !self.is_absolute() }
+
+    /// Returns the last body component and its offset.
+    fn last_body(&self) -> Option<(WComponent<'_>, usize)> {
+        let mut c = WComponents::new(&self.inner);
+        let mut last = None;
+        while let Some(x) = c.next_body() {
+            last = Some(x);
+        }
+        last
+    }
+
+    /// Returns the path without its last component.
+    ///
+    /// Returns `None` if the path is empty, or consists of a prefix and the
+    /// root only.
+    pub fn parent(&self) -> Option<&WPath> {
+        let (_, offset) = self.last_body()?;
+        let c = WComponents::new(&self.inner);
+        let body_start = self.inner.len() - c.body.len();
+        let root_end = if c.root && !c.body.is_empty() {
+            body_start + 1
+        } else {
+            body_start
+        };
+        let mut end = offset;
+        while end > root_end && is_sep(self.inner[end - 1], c.verbatim) {
+            end -= 1;
+        }
+        Some(unsafe { WPath::from_wide_unchecked(&self.inner[..end]) })
+    }
+
+    /// Returns the last component if it is a normal component.
+    pub fn file_name(&self) -> Option<&WPath> {
+        match self.last_body()?.0 {
+            WComponent::Normal(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Creates a path that `path` is joined to `self`.
+    ///
+    /// See [`WPathBuf::push`] for the details.
+    pub fn join<P: AsRef<WPath>>(&self, path: P) -> WPathBuf {
+        let mut ret = self.to_wpath_buf();
+        ret.push(path);
+        ret
+    }
+
+    /// Normalizes the path lexically.
+    ///
+    /// - `/` is replaced with `\`, and consecutive separators are merged.
+    /// - `.` is removed.
+    /// - `..` removes the previous component. `..` at the root is removed.
+    /// - A trailing separator is removed.
+    /// - The root directory is written only if the path has a separator after
+    ///   the prefix, so `\\.\COM1` and `\\server` are kept as they are.
+    ///
+    /// A verbatim or NT path is returned unchanged. An empty path is
+    /// normalized to `.`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WPath, wstr};
+    ///
+    /// let p = WPath::new(wstr!(r"C:/a/.\b\..\..\..\c\")).normalize();
+    /// assert_eq!(p.to_string_lossy(), r"C:\c");
+    /// ```
+    pub fn normalize(&self) -> WPathBuf {
+        let mut c = self.components();
+        if c.verbatim {
+            return self.to_wpath_buf();
+        }
+        let root = c.root;
+        let prefix = &self.inner[..c.offset];
+        let has_sep =
+            self.inner.get(c.offset).is_some_and(|&x| is_sep(x, false));
+        let mut v = Vec::with_capacity(self.inner.len());
+        let mut parts: Vec<WComponent<'_>> = Vec::new();
+        for x in &mut c {
+            match x {
+                WComponent::Prefix(p) => push_prefix(&mut v, p, prefix),
+                WComponent::RootDir if has_sep => v.push(BACKSLASH),
+                WComponent::RootDir => {}
+                WComponent::CurDir => {}
+                WComponent::ParentDir => match parts.last() {
+                    Some(WComponent::Normal(_)) => {
+                        parts.pop();
+                    }
+                    _ if root => {}
+                    _ => parts.push(x),
+                },
+                WComponent::Normal(_) => parts.push(x),
+            }
+        }
+        for (i, x) in parts.iter().enumerate() {
+            if i != 0 {
+                v.push(BACKSLASH);
+            }
+            match x {
+                WComponent::Normal(x) => v.extend_from_slice(&x.inner),
+                _ => v.extend_from_slice(&[DOT, DOT]),
+            }
+        }
+        if v.is_empty() {
+            v.push(DOT);
+        }
+        WPathBuf::from(unsafe { WString::new_unchecked(v) })
+    }
+}
+
+/// Appends the normalized representation of `p` parsed from `src` to `v`.
+fn push_prefix(v: &mut Vec<u16>, p: WPrefix<'_>, src: &[u16]) {
+    match p {
+        WPrefix::DeviceNS(x) => {
+            v.extend_from_slice(&[BACKSLASH, BACKSLASH, DOT, BACKSLASH]);
+            v.extend_from_slice(&x.inner);
+        }
+        WPrefix::UNC(server, share) => {
+            v.extend_from_slice(&[BACKSLASH, BACKSLASH]);
+            v.extend_from_slice(&server.inner);
+            // `\\server` has no separator after the server name.
+            if src.len() > 2 + server.inner.len() {
+                v.push(BACKSLASH);
+                v.extend_from_slice(&share.inner);
+            }
+        }
+        WPrefix::Disk(x) => v.extend_from_slice(&[x as u16, COLON]),
+        // Verbatim paths are not normalized.
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the kind, the drive letter and the slices of `c` to compare
+/// components without comparing [`WPath`]s recursively.
+///
+/// Drive letters are uppercased since they are case-insensitive.
+fn raw_parts<'a>(c: WComponent<'a>) -> (u8, u8, &'a [u16], &'a [u16]) {
+    match c {
+        WComponent::Prefix(p) => match p {
+            WPrefix::Verbatim(x) => (0, 0, &x.inner, &[]),
+            WPrefix::VerbatimUNC(x, y) => (1, 0, &x.inner, &y.inner),
+            WPrefix::VerbatimDisk(d) => (2, d.to_ascii_uppercase(), &[], &[]),
+            WPrefix::DeviceNS(x) => (3, 0, &x.inner, &[]),
+            WPrefix::UNC(x, y) => (4, 0, &x.inner, &y.inner),
+            WPrefix::Disk(d) => (5, d.to_ascii_uppercase(), &[], &[]),
+            WPrefix::Nt(x) => (6, 0, &x.inner, &[]),
+        },
+        WComponent::RootDir => (7, 0, &[], &[]),
+        WComponent::CurDir => (8, 0, &[], &[]),
+        WComponent::ParentDir => (9, 0, &[], &[]),
+        WComponent::Normal(x) => (10, 0, &x.inner, &[]),
+    }
+}
+
+impl PartialEq for WPath {
+    /// Compares paths by their components, so `C:/a` and `c:\a\` are equal.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.components()
+            .map(raw_parts)
+            .eq(other.components().map(raw_parts))
+    }
+}
+
+impl Eq for WPath {}
+
+impl Hash for WPath {
+    // Consistent with `PartialEq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in self.components() {
+            raw_parts(x).hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for WPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl AsRef<WPath> for WPath {
+    #[inline]
+    fn as_ref(&self) -> &WPath { self }
+}
+
+impl AsRef<WPath> for WStr {
+    #[inline]
+    fn as_ref(&self) -> &WPath {
+        unsafe { WPath::from_wide_unchecked(self.to_bytes()) }
+    }
+}
+
+impl AsRef<WPath> for WString {
+    #[inline]
+    fn as_ref(&self) -> &WPath { (**self).as_ref() }
+}
+
+impl Default for &WPath {
+    #[inline]
+    fn default() -> Self { unsafe { WPath::from_wide_unchecked(&[]) } }
+}
+
+impl ToOwned for WPath {
+    type Owned = WPathBuf;
+
+    #[inline]
+    fn to_owned(&self) -> WPathBuf { self.to_wpath_buf() }
+}
+
+/// An owned Windows path.
+///
+/// The path is null-terminated, so it can be passed to Windows APIs by
+/// [`WPathBuf::as_wstr`].
+#[derive(Clone, Default)]
+pub struct WPathBuf {
+    inner: WString,
+}
+
+impl WPathBuf {
+    /// Creates an empty path.
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Converts `s` to [`WPathBuf`].
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> ConvertResult<Self> {
+        WString::from_str(s).map(Self::from)
+    }
+
+    /// Returns the path as [`WPath`].
+    #[inline]
+    pub fn as_path(&self) -> &WPath { self.inner.as_ref() }
+
+    /// Returns the path as [`WStr`].
+    #[inline]
+    pub fn as_wstr(&self) -> &WStr { &self.inner }
+
+    /// Converts the path to [`WString`].
+    #[inline]
+    pub fn into_wstring(self) -> WString { self.inner }
+
+    /// Appends `path` to `self`.
+    ///
+    /// - If `path` is absolute or has a prefix, `self` is replaced with
+    ///   `path`.
+    /// - If `path` has the root but no prefix, such as `\a`, `path`
+    ///   replaces everything except the prefix of `self`.
+    /// - Otherwise, `path` is appended with a separator `\` if needed.
+    ///
+    /// If `self` is a verbatim or NT path, `.` and `..` of `path` are
+    /// resolved and `/` is replaced with `\` like [`std::path::PathBuf`],
+    /// since Windows does not normalize such paths.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{WPathBuf, wstr};
+    ///
+    /// let mut p = WPathBuf::from_str(r"C:\a").unwrap();
+    /// p.push(wstr!("b"));
+    /// assert_eq!(p.to_string_lossy(), r"C:\a\b");
+    /// p.push(wstr!(r"\c"));
+    /// assert_eq!(p.to_string_lossy(), r"C:\c");
+    /// p.push(wstr!(r"D:d"));
+    /// assert_eq!(p.to_string_lossy(), r"D:d");
+    /// ```
+    pub fn push<P: AsRef<WPath>>(&mut self, path: P) {
+        let path = path.as_ref();
+        if self.prefix().is_some_and(|x| x.is_verbatim())
+            && !path.is_absolute()
+            && path.prefix().is_none()
+        {
+            return self.push_verbatim(path);
+        }
+        let s = self.as_wide();
+        let mut v = if path.is_absolute() || path.prefix().is_some() {
+            Vec::new()
+        } else if path.has_root() {
+            let n = parse_prefix(s).map_or(0, |(_, n)| n);
+            s[..n].to_vec()
+        } else {
+            let mut v = s.to_vec();
+            let verbatim = self.prefix().is_some_and(|x| x.is_verbatim());
+            let disk_only = matches!(
+                parse_prefix(s),
+                Some((WPrefix::Disk(_), n)) if n == s.len()
+            );
+            if !s.is_empty() && !is_sep(s[s.len() - 1], verbatim) && !disk_only
+            {
+                v.push(BACKSLASH);
+            }
+            v
+        };
+        v.extend_from_slice(path.as_wide());
+        self.inner = unsafe { WString::new_unchecked(v) };
+    }
+
+    /// Appends the components of `path`, which has no prefix, to a verbatim
+    /// path.
+    fn push_verbatim(&mut self, path: &WPath) {
+        for c in path.components() {
+            match c {
+                WComponent::RootDir => {
+                    let s = self.as_wide();
+                    let n = parse_prefix(s).map_or(0, |(_, n)| n);
+                    let mut v = s[..n].to_vec();
+                    v.push(BACKSLASH);
+                    self.inner = unsafe { WString::new_unchecked(v) };
+                }
+                WComponent::ParentDir => {
+                    if self.file_name().is_some() {
+                        self.pop();
+                    }
+                }
+                WComponent::Normal(x) => {
+                    let mut v = self.as_wide().to_vec();
+                    if v.last() != Some(&BACKSLASH) {
+                        v.push(BACKSLASH);
+                    }
+                    v.extend_from_slice(x.as_wide());
+                    self.inner = unsafe { WString::new_unchecked(v) };
+                }
+                WComponent::Prefix(_) | WComponent::CurDir => {}
+            }
+        }
+    }
+
+    /// Removes the last component.
+    ///
+    /// Returns `false` if the path has no parent.
+    pub fn pop(&mut self) -> bool {
+        match self.parent() {
+            Some(x) => {
+                *self = x.to_wpath_buf();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ops::Deref for WPathBuf {
+    type Target = WPath;
+
+    #[inline]
+    fn deref(&self) -> &WPath { self.as_path() }
+}
+
+impl Borrow<WPath> for WPathBuf {
+    #[inline]
+    fn borrow(&self) -> &WPath { self }
+}
+
+impl AsRef<WPath> for WPathBuf {
+    #[inline]
+    fn as_ref(&self) -> &WPath { self }
+}
+
+impl PartialEq for WPathBuf {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool { self.as_path() == other.as_path() }
+}
+
+impl Eq for WPathBuf {}
+
+impl Hash for WPathBuf {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) { self.as_path().hash(state) }
+}
+
+impl fmt::Debug for WPathBuf {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path(), f)
+    }
+}
+
+impl From<WString> for WPathBuf {
+    #[inline]
+    fn from(x: WString) -> Self { Self { inner: x } }
+}
+
+impl From<&WStr> for WPathBuf {
+    #[inline]
+    fn from(x: &WStr) -> Self { Self::from(x.to_wstring()) }
+}
+
+impl From<&WPath> for WPathBuf {
+    #[inline]
+    fn from(x: &WPath) -> Self { x.to_wpath_buf() }
+}
+
+impl From<WPathBuf> for WString {
+    #[inline]
+    fn from(x: WPathBuf) -> Self { x.inner }
+}
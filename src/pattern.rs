@@ -0,0 +1,528 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! Searching and slicing of [`WStr`] and [`AStr`].
+//!
+//! Sub-slices in the middle of a string are not null-terminated, so most
+//! functions return `&[u16]` or `&[u8]` instead of `&WStr` or `&AStr`.
+use crate::*;
+
+/// A pattern that can be searched for in [`WStr`].
+///
+/// This trait is implemented for `char`, `u16`, `&[u16]`, `&WStr`, `&WString`
+/// and `&str`.
+pub trait WStrPattern {
+    /// Code units of the pattern.
+    type Needle: AsRef<[u16]>;
+
+    /// Converts the pattern to UTF-16 code units.
+    fn into_needle(self) -> Self::Needle;
+}
+
+/// A pattern that can be searched for in [`AStr`].
+///
+/// This trait is implemented for `u8`, `&[u8]`, `&AStr`, `&AString`, `char`
+/// and `&str`.
+///
+/// Patterns are matched byte by byte. A `char` or `&str` pattern that
+/// contains a non-ASCII character never matches, since the encoding of such
+/// a character depends on the ANSI code page.
+///
+/// Since the code page is not considered, an ASCII pattern can match the
+/// trail byte of a double-byte character. [`AStr::is_char_boundary`] tells
+/// whether a match starts at a character boundary.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{AStr, CP_SHIFT_JIS};
+/// // "ソ" (0x83 0x5C) in Shift_JIS.
+/// let s = AStr::from_bytes_with_nul(b"\x83\x5C\0").unwrap();
+/// assert_eq!(s.find('\\'), Some(1));
+/// assert!(!s.is_char_boundary(1, CP_SHIFT_JIS));
+/// ```
+pub trait AStrPattern {
+    /// Bytes of the pattern.
+    type Needle: AsRef<[u8]>;
+
+    /// Converts the pattern to bytes.
+    ///
+    /// Returns `None` if the pattern cannot be encoded, which never matches.
+    fn into_needle(self) -> Option<Self::Needle>;
+}
+
+/// Code units of a `char` or a `u16`.
+#[doc(hidden)]
+#[derive(Copy, Clone, Debug)]
+pub struct CharNeedle {
+    buf: [u16; 2],
+    len: usize,
+}
+
+impl AsRef<[u16]> for CharNeedle {
+    #[inline]
+    fn as_ref(&self) -> &[u16] { &self.buf[..self.len] }
+}
+
+impl WStrPattern for char {
+    type Needle = CharNeedle;
+
+    #[inline]
+    fn into_needle(self) -> CharNeedle {
+        let mut buf = [0; 2];
+        let len = self.encode_utf16(&mut buf).len();
+        CharNeedle { buf, len }
+    }
+}
+
+impl WStrPattern for u16 {
+    type Needle = CharNeedle;
+
+    #[inline]
+    fn into_needle(self) -> CharNeedle {
+        CharNeedle {
+            buf: [self, 0],
+            len: 1,
+        }
+    }
+}
+
+impl<'a> WStrPattern for &'a [u16] {
+    type Needle = &'a [u16];
+
+    #[inline]
+    fn into_needle(self) -> &'a [u16] { self }
+}
+
+impl<'a> WStrPattern for &'a WStr {
+    type Needle = &'a [u16];
+
+    #[inline]
+    fn into_needle(self) -> &'a [u16] { self.to_bytes() }
+}
+
+#[cfg(feature = "std")]
+impl<'a> WStrPattern for &'a WString {
+    type Needle = &'a [u16];
+
+    #[inline]
+    fn into_needle(self) -> &'a [u16] { self.as_bytes() }
+}
+
+#[cfg(feature = "std")]
+impl WStrPattern for &str {
+    type Needle = Vec<u16>;
+
+    #[inline]
+    fn into_needle(self) -> Vec<u16> { self.encode_utf16().collect() }
+}
+
+impl AStrPattern for u8 {
+    type Needle = [u8; 1];
+
+    #[inline]
+    fn into_needle(self) -> Option<[u8; 1]> { Some([self]) }
+}
+
+impl AStrPattern for char {
+    type Needle = [u8; 1];
+
+    #[inline]
+    fn into_needle(self) -> Option<[u8; 1]> {
+        self.is_ascii().then_some([self as u8])
+    }
+}
+
+impl<'a> AStrPattern for &'a [u8] {
+    type Needle = &'a [u8];
+
+    #[inline]
+    fn into_needle(self) -> Option<&'a [u8]> { Some(self) }
+}
+
+impl<'a> AStrPattern for &'a AStr {
+    type Needle = &'a [u8];
+
+    #[inline]
+    fn into_needle(self) -> Option<&'a [u8]> { Some(self.to_bytes()) }
+}
+
+#[cfg(feature = "std")]
+impl<'a> AStrPattern for &'a AString {
+    type Needle = &'a [u8];
+
+    #[inline]
+    fn into_needle(self) -> Option<&'a [u8]> { Some(self.as_bytes()) }
+}
+
+impl<'a> AStrPattern for &'a str {
+    type Needle = &'a [u8];
+
+    #[inline]
+    fn into_needle(self) -> Option<&'a [u8]> {
+        self.is_ascii().then_some(self.as_bytes())
+    }
+}
+
+/// Returns the first position of `needle` in `haystack`.
+fn find_slice<T: PartialEq>(haystack: &[T], needle: &[T]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack.windows(needle.len()).position(|x| x == needle)
+}
+
+/// Returns the last position of `needle` in `haystack`.
+fn rfind_slice<T: PartialEq>(haystack: &[T], needle: &[T]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(haystack.len());
+    }
+    haystack.windows(needle.len()).rposition(|x| x == needle)
+}
+
+/// An iterator over sub-slices separated by a pattern.
+///
+/// This struct is created by [`WStr::split`] and [`AStr::split`].
+#[derive(Clone, Debug)]
+pub struct Split<'a, T, N> {
+    rest: Option<&'a [T]>,
+    needle: Option<N>,
+}
+
+impl<'a, T: PartialEq, N: AsRef<[T]>> Iterator for Split<'a, T, N> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        let needle = self.needle.as_ref().map_or(&[][..], AsRef::as_ref);
+        match find_slice(rest, needle) {
+            Some(i) if !needle.is_empty() => {
+                self.rest = Some(&rest[i + needle.len()..]);
+                Some(&rest[..i])
+            }
+            _ => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// An iterator over lines.
+///
+/// This struct is created by [`WStr::lines`] and [`AStr::lines`].
+#[derive(Clone, Debug)]
+pub struct Lines<'a, T> {
+    rest: &'a [T],
+}
+
+macro_rules! impl_lines {
+    ($t:ty) => {
+        impl<'a> Iterator for Lines<'a, $t> {
+            type Item = &'a [$t];
+
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.rest.is_empty() {
+                    return None;
+                }
+                let (line, rest) =
+                    match self.rest.iter().position(|&x| x == b'\n' as $t) {
+                        Some(i) => (&self.rest[..i], &self.rest[i + 1..]),
+                        None => (self.rest, &self.rest[self.rest.len()..]),
+                    };
+                self.rest = rest;
+                match line.split_last() {
+                    Some((&x, line)) if x == b'\r' as $t => Some(line),
+                    _ => Some(line),
+                }
+            }
+        }
+    };
+}
+
+impl_lines!(u16);
+impl_lines!(u8);
+
+/// Returns `true` if `x` is a white space character.
+fn is_wide_whitespace(x: u16) -> bool {
+    // All white space characters are in the BMP.
+    char::from_u32(x as u32).is_some_and(char::is_whitespace)
+}
+
+/// Replaces all `from` in `haystack` with `to`.
+///
+/// An empty `from` matches nothing.
+#[cfg(feature = "std")]
+fn replace_slice<T: PartialEq + Copy>(
+    haystack: &[T],
+    from: &[T],
+    to: &[T],
+) -> Vec<T> {
+    if from.is_empty() {
+        return haystack.to_vec();
+    }
+    let mut ret = Vec::with_capacity(haystack.len());
+    let mut rest = haystack;
+    while let Some(i) = find_slice(rest, from) {
+        ret.extend_from_slice(&rest[..i]);
+        ret.extend_from_slice(to);
+        rest = &rest[i + from.len()..];
+    }
+    ret.extend_from_slice(rest);
+    ret
+}
+
+impl WStr {
+    /// Returns the position of the first match of `pat` in code units.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    /// let s = wstr!("HKLM\\SOFTWARE\\Microsoft");
+    /// assert_eq!(s.find('\\'), Some(4));
+    /// assert_eq!(s.find(wstr!("Microsoft")), Some(14));
+    /// assert_eq!(s.rfind(wstr!("\\")), Some(13));
+    /// ```
+    pub fn find<P: WStrPattern>(&self, pat: P) -> Option<usize> {
+        find_slice(self.to_bytes(), pat.into_needle().as_ref())
+    }
+
+    /// Returns the position of the last match of `pat` in code units.
+    pub fn rfind<P: WStrPattern>(&self, pat: P) -> Option<usize> {
+        rfind_slice(self.to_bytes(), pat.into_needle().as_ref())
+    }
+
+    /// Returns `true` if `pat` matches a sub-slice of the string.
+    #[inline]
+    pub fn contains<P: WStrPattern>(&self, pat: P) -> bool {
+        self.find(pat).is_some()
+    }
+
+    /// Returns `true` if `pat` matches a prefix of the string.
+    pub fn starts_with<P: WStrPattern>(&self, pat: P) -> bool {
+        self.to_bytes().starts_with(pat.into_needle().as_ref())
+    }
+
+    /// Returns `true` if `pat` matches a suffix of the string.
+    pub fn ends_with<P: WStrPattern>(&self, pat: P) -> bool {
+        self.to_bytes().ends_with(pat.into_needle().as_ref())
+    }
+
+    /// Returns an iterator over sub-slices separated by `pat`.
+    ///
+    /// If `pat` is empty, the whole string is returned as one sub-slice.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    /// let v: Vec<&[u16]> = wstr!("a;b").split(';').collect();
+    /// assert_eq!(v, [&[0x61][..], &[0x62][..]]);
+    /// ```
+    pub fn split<P: WStrPattern>(&self, pat: P) -> Split<'_, u16, P::Needle> {
+        Split {
+            rest: Some(self.to_bytes()),
+            needle: Some(pat.into_needle()),
+        }
+    }
+
+    /// Splits the string on the first match of `pat`.
+    ///
+    /// The part after `pat` is returned as [`WStr`] since it is
+    /// null-terminated.
+    pub fn split_once<P: WStrPattern>(
+        &self,
+        pat: P,
+    ) -> Option<(&[u16], &Self)> {
+        let needle = pat.into_needle();
+        let needle = needle.as_ref();
+        let i = find_slice(self.to_bytes(), needle)?;
+        let bytes = self.to_bytes_with_nul();
+        let rest = unsafe {
+            Self::from_bytes_with_nul_unchecked(&bytes[i + needle.len()..])
+        };
+        Some((&bytes[..i], rest))
+    }
+
+    /// Returns the string without leading and trailing white spaces.
+    pub fn trim(&self) -> &[u16] { trim_end(self.trim_start().to_bytes()) }
+
+    /// Returns the string without leading white spaces.
+    pub fn trim_start(&self) -> &Self {
+        let bytes = self.to_bytes_with_nul();
+        let i = bytes
+            .iter()
+            .position(|&x| !is_wide_whitespace(x))
+            .unwrap_or(bytes.len() - 1);
+        unsafe { Self::from_bytes_with_nul_unchecked(&bytes[i..]) }
+    }
+
+    /// Returns the string without trailing white spaces.
+    pub fn trim_end(&self) -> &[u16] { trim_end(self.to_bytes()) }
+
+    /// Returns an iterator over lines.
+    ///
+    /// Lines are split at `\n`, and a trailing `\r` of each line is removed.
+    pub fn lines(&self) -> Lines<'_, u16> {
+        Lines {
+            rest: self.to_bytes(),
+        }
+    }
+
+    /// Replaces all matches of `from` with `to`.
+    ///
+    /// Unlike [`str::replace`], an empty `from` matches nothing, and the
+    /// string is returned unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` contains a null character.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::wstr;
+    /// let s = wstr!("C:/Windows/System32").replace('/', '\\');
+    /// assert_eq!(s.to_string_lossy(), "C:\\Windows\\System32");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn replace<P: WStrPattern, Q: WStrPattern>(
+        &self,
+        from: P,
+        to: Q,
+    ) -> WString {
+        let to = to.into_needle();
+        let to = to.as_ref();
+        assert!(!to.contains(&0), "replacement contains a null character");
+        let v = replace_slice(self.to_bytes(), from.into_needle().as_ref(), to);
+        unsafe { WString::new_unchecked(v) }
+    }
+}
+
+/// Returns `x` without trailing white spaces.
+fn trim_end(x: &[u16]) -> &[u16] {
+    let i = x
+        .iter()
+        .rposition(|&x| !is_wide_whitespace(x))
+        .map_or(0, |i| i + 1);
+    &x[..i]
+}
+
+impl AStr {
+    /// Returns the position of the first match of `pat` in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::astr;
+    /// let s = astr!("HKLM\\SOFTWARE");
+    /// assert_eq!(s.find('\\'), Some(4));
+    /// assert_eq!(s.find("SOFT"), Some(5));
+    /// ```
+    pub fn find<P: AStrPattern>(&self, pat: P) -> Option<usize> {
+        find_slice(self.to_bytes(), pat.into_needle()?.as_ref())
+    }
+
+    /// Returns the position of the last match of `pat` in bytes.
+    pub fn rfind<P: AStrPattern>(&self, pat: P) -> Option<usize> {
+        rfind_slice(self.to_bytes(), pat.into_needle()?.as_ref())
+    }
+
+    /// Returns `true` if `pat` matches a sub-slice of the string.
+    #[inline]
+    pub fn contains<P: AStrPattern>(&self, pat: P) -> bool {
+        self.find(pat).is_some()
+    }
+
+    /// Returns `true` if `pat` matches a prefix of the string.
+    pub fn starts_with<P: AStrPattern>(&self, pat: P) -> bool {
+        pat.into_needle()
+            .is_some_and(|x| self.to_bytes().starts_with(x.as_ref()))
+    }
+
+    /// Returns `true` if `pat` matches a suffix of the string.
+    pub fn ends_with<P: AStrPattern>(&self, pat: P) -> bool {
+        pat.into_needle()
+            .is_some_and(|x| self.to_bytes().ends_with(x.as_ref()))
+    }
+
+    /// Returns an iterator over sub-slices separated by `pat`.
+    ///
+    /// If `pat` is empty, the whole string is returned as one sub-slice.
+    pub fn split<P: AStrPattern>(&self, pat: P) -> Split<'_, u8, P::Needle> {
+        Split {
+            rest: Some(self.to_bytes()),
+            needle: pat.into_needle(),
+        }
+    }
+
+    /// Splits the string on the first match of `pat`.
+    ///
+    /// The part after `pat` is returned as [`AStr`] since it is
+    /// null-terminated.
+    pub fn split_once<P: AStrPattern>(&self, pat: P) -> Option<(&[u8], &Self)> {
+        let needle = pat.into_needle()?;
+        let needle = needle.as_ref();
+        let i = find_slice(self.to_bytes(), needle)?;
+        let bytes = self.to_bytes_with_nul();
+        let rest = unsafe {
+            Self::from_bytes_with_nul_unchecked(&bytes[i + needle.len()..])
+        };
+        Some((&bytes[..i], rest))
+    }
+
+    /// Returns the string without leading and trailing ASCII white spaces.
+    pub fn trim(&self) -> &[u8] {
+        self.trim_start().to_bytes().trim_ascii_end()
+    }
+
+    /// Returns the string without leading ASCII white spaces.
+    pub fn trim_start(&self) -> &Self {
+        let bytes = self.to_bytes_with_nul();
+        let i = bytes
+            .iter()
+            .position(|x| !x.is_ascii_whitespace())
+            .unwrap_or(bytes.len() - 1);
+        unsafe { Self::from_bytes_with_nul_unchecked(&bytes[i..]) }
+    }
+
+    /// Returns the string without trailing ASCII white spaces.
+    pub fn trim_end(&self) -> &[u8] { self.to_bytes().trim_ascii_end() }
+
+    /// Returns an iterator over lines.
+    ///
+    /// Lines are split at `\n`, and a trailing `\r` of each line is removed.
+    pub fn lines(&self) -> Lines<'_, u8> {
+        Lines {
+            rest: self.to_bytes(),
+        }
+    }
+
+    /// Replaces all matches of `from` with `to`.
+    ///
+    /// Unlike [`str::replace`], an empty `from` matches nothing, and the
+    /// string is returned unchanged. Matches are not checked against the
+    /// character boundaries of the code page, as described in
+    /// [`AStrPattern`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` contains a null character or a non-ASCII `char`.
+    #[cfg(feature = "std")]
+    pub fn replace<P: AStrPattern, Q: AStrPattern>(
+        &self,
+        from: P,
+        to: Q,
+    ) -> AString {
+        let to = to
+            .into_needle()
+            .expect("replacement contains a non-ASCII character");
+        let to = to.as_ref();
+        assert!(!to.contains(&0), "replacement contains a null character");
+        let v = match from.into_needle() {
+            Some(from) => replace_slice(self.to_bytes(), from.as_ref(), to),
+            None => self.to_bytes().to_vec(),
+        };
+        unsafe { AString::new_unchecked(v) }
+    }
+}
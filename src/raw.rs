@@ -13,10 +13,15 @@ pub(crate) type c_int = i32;
 pub(crate) type c_uint = u32;
 pub(crate) type c_ulong = u32;
 pub(crate) type wchar_t = u16;
+pub(crate) type c_void = core::ffi::c_void;
 
 pub(crate) type USHORT = c_ushort;
 pub(crate) type UINT = c_uint;
 pub(crate) type DWORD = c_ulong;
+pub(crate) type BOOL = c_int;
+pub(crate) type HANDLE = *mut c_void;
+pub(crate) type HLOCAL = HANDLE;
+pub(crate) type LPVOID = *mut c_void;
 pub(crate) type LPBOOL = *mut c_int;
 pub(crate) type LPSTR = *mut c_char;
 pub(crate) type LPCSTR = *const c_char;
@@ -44,15 +49,41 @@ pub struct ANSI_STRING {
 }
 
 unsafe extern "C" {
+    #[cfg(windows)]
     pub(crate) fn wcslen(s: *const wchar_t) -> usize;
 
     pub(crate) fn strlen(s: *const u8) -> usize;
 
+    #[cfg(windows)]
     pub(crate) fn wcsnlen(s: *const wchar_t, len: usize) -> usize;
 
     pub(crate) fn strnlen(s: *const u8, len: usize) -> usize;
 }
 
+// `wchar_t` of the C library is not 16-bit on non-Windows hosts.
+#[cfg(not(windows))]
+pub(crate) unsafe fn wcslen(s: *const wchar_t) -> usize {
+    let mut len = 0;
+    unsafe {
+        while *s.add(len) != 0 {
+            len += 1;
+        }
+    }
+    len
+}
+
+#[cfg(not(windows))]
+pub(crate) unsafe fn wcsnlen(s: *const wchar_t, len: usize) -> usize {
+    let mut i = 0;
+    unsafe {
+        while i < len && *s.add(i) != 0 {
+            i += 1;
+        }
+    }
+    i
+}
+
+#[cfg(windows)]
 unsafe extern "system" {
     pub(crate) fn MultiByteToWideChar(
         CodePage: UINT,
@@ -77,6 +108,7 @@ unsafe extern "system" {
     pub(crate) fn GetLastError() -> DWORD;
 }
 
+#[cfg(windows)]
 #[link(name = "ntdll")]
 unsafe extern "system" {
     pub(crate) fn RtlInitUnicodeString(
@@ -89,3 +121,44 @@ unsafe extern "system" {
         SourceString: PCSTR,
     );
 }
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    pub(crate) fn LocalFree(hMem: HLOCAL) -> HLOCAL;
+
+    pub(crate) fn GetProcessHeap() -> HANDLE;
+
+    pub(crate) fn HeapAlloc(
+        hHeap: HANDLE,
+        dwFlags: DWORD,
+        dwBytes: usize,
+    ) -> LPVOID;
+
+    pub(crate) fn HeapFree(
+        hHeap: HANDLE,
+        dwFlags: DWORD,
+        lpMem: LPVOID,
+    ) -> BOOL;
+
+    pub(crate) fn GetACP() -> UINT;
+
+    pub(crate) fn GetOEMCP() -> UINT;
+}
+
+#[cfg(windows)]
+#[link(name = "ole32")]
+unsafe extern "system" {
+    pub(crate) fn CoTaskMemFree(pv: LPVOID);
+}
+
+#[cfg(windows)]
+#[link(name = "oleaut32")]
+unsafe extern "system" {
+    pub(crate) fn SysAllocStringLen(
+        strIn: *const wchar_t,
+        ui: UINT,
+    ) -> *mut wchar_t;
+
+    pub(crate) fn SysFreeString(bstrString: *mut wchar_t);
+}
@@ -4,22 +4,30 @@ use crate::{
     __lib::{
         convert::{TryFrom, TryInto},
         fmt::Write,
-        ops, slice,
+        mem, ops, ptr, slice,
     },
     convert::*,
     *,
 };
+use std::{
+    borrow::{Borrow, Cow},
+    rc::Rc,
+    sync::Arc,
+};
 
 macro_rules! str_impl_debug {
     ($x:ident) => {
+        str_impl_debug!($x, feature = "std");
+    };
+    ($x:ident, $lossy:meta) => {
         impl fmt::Debug for $x {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_char('"')?;
-                #[cfg(not(feature = "std"))]
+                #[cfg(not($lossy))]
                 {
                     fmt::Debug::fmt(&self.to_bytes_with_nul(), f)?;
                 }
-                #[cfg(feature = "std")]
+                #[cfg($lossy)]
                 {
                     fmt::Display::fmt(&self.to_string_lossy(), f)?;
                 }
@@ -28,11 +36,11 @@ macro_rules! str_impl_debug {
         }
         impl fmt::Display for $x {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                #[cfg(not(feature = "std"))]
+                #[cfg(not($lossy))]
                 {
                     fmt::Debug::fmt(&self.to_bytes_with_nul(), f)
                 }
-                #[cfg(feature = "std")]
+                #[cfg($lossy)]
                 {
                     fmt::Display::fmt(&self.to_string_lossy(), f)
                 }
@@ -48,6 +56,129 @@ fn concat_slice<T: Clone>(x: &[T], y: &[T]) -> Vec<T> {
     inner
 }
 
+/// Returns the position of the first null character in `v`.
+fn nul_position<T: Default + PartialEq>(v: &[T]) -> Option<usize> {
+    let nul = T::default();
+    v.iter().position(|x| *x == nul)
+}
+
+/// An error indicating that an interior null character was found.
+///
+/// This error is returned by [`WString::new`] and [`AString::new`].
+/// The original vector can be taken back by [`NulError::into_vec`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NulError<T>(usize, Vec<T>);
+
+impl<T> NulError<T> {
+    /// Returns the position of the null character.
+    #[inline]
+    pub fn nul_position(&self) -> usize { self.0 }
+
+    /// Returns the vector that was passed to the constructor.
+    #[inline]
+    pub fn into_vec(self) -> Vec<T> { self.1 }
+}
+
+impl<T> fmt::Display for NulError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nul found in provided data at position: {}", self.0)
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for NulError<T> {}
+
+/// An error indicating that a vector was not a null-terminated string.
+///
+/// This error is returned by [`WString::from_vec_with_nul`] and
+/// [`AString::from_vec_with_nul`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FromVecWithNulError<T> {
+    error: FromBytesWithNulError,
+    bytes: Vec<T>,
+}
+
+impl<T> FromVecWithNulError<T> {
+    /// Returns the kind and the position of the error.
+    #[inline]
+    pub fn error(&self) -> FromBytesWithNulError { self.error }
+
+    /// Returns the vector that was passed to the constructor.
+    #[inline]
+    pub fn as_bytes(&self) -> &[T] { &self.bytes }
+
+    /// Returns the vector that was passed to the constructor.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<T> { self.bytes }
+}
+
+impl<T> fmt::Display for FromVecWithNulError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for FromVecWithNulError<T> {}
+
+/// Returns the position of the first unpaired surrogate in `v`.
+fn unpaired_surrogate_position(v: &[u16]) -> Option<usize> {
+    let mut i = 0;
+    while i < v.len() {
+        match v[i] {
+            0xD800..=0xDBFF => {
+                if !matches!(v.get(i + 1), Some(0xDC00..=0xDFFF)) {
+                    return Some(i);
+                }
+                i += 2;
+            }
+            0xDC00..=0xDFFF => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// An error indicating that bytes could not be converted to [`WString`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FromU16BytesError {
+    /// The length of the bytes is odd.
+    OddLength { len: usize },
+    /// The string has an unpaired surrogate at `position` in code units.
+    UnpairedSurrogate { position: usize },
+}
+
+impl fmt::Display for FromU16BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OddLength { len } => {
+                write!(f, "the length of the bytes is odd: {}", len)
+            }
+            Self::UnpairedSurrogate { position } => {
+                write!(f, "unpaired surrogate found at position: {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FromU16BytesError {}
+
+/// Cuts `b` at the first null character and terminates it, so that the
+/// length of the allocation is the length of the string plus one.
+///
+/// The buffer may have lost its canonical form through
+/// `as_bytes_with_nul_mut`.
+fn into_nul_terminated<T: Copy + Default + PartialEq>(b: Box<[T]>) -> Box<[T]> {
+    let nul = T::default();
+    match b.iter().position(|&x| x == nul) {
+        Some(n) if n + 1 == b.len() => b,
+        n => {
+            let mut v = b.into_vec();
+            v.truncate(n.unwrap_or(v.len()));
+            v.push(nul);
+            v.into_boxed_slice()
+        }
+    }
+}
+
 /// Represents a wide string (Unicode string).
 #[repr(C)]
 #[derive(Clone, PartialOrd, PartialEq, Eq, Ord, Hash)]
@@ -104,6 +235,67 @@ impl WString {
     #[inline]
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
+    /// Creates [`WString`] from `v`.
+    ///
+    /// A null terminator is appended to `v`. If `v` contains a null
+    /// character, this function returns [`NulError`] that holds its position
+    /// and `v`.
+    ///
+    /// No encoding checks are performed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WString;
+    /// let s = WString::new(vec![0x0074, 0x0065, 0x0073, 0x0074]).unwrap();
+    /// assert_eq!(s.as_bytes().len(), 4);
+    /// let e = WString::new(vec![0x0074, 0x0000, 0x0074]).unwrap_err();
+    /// assert_eq!(e.nul_position(), 1);
+    /// ```
+    pub fn new<T: Into<Vec<u16>>>(v: T) -> Result<Self, NulError<u16>> {
+        let mut v = v.into();
+        if let Some(i) = nul_position(&v) {
+            return Err(NulError(i, v));
+        }
+        v.reserve_exact(1);
+        v.push(0);
+        unsafe { Ok(Self::new_nul_unchecked(v)) }
+    }
+
+    /// Creates [`WString`] from `v` that ends with a null terminator.
+    ///
+    /// If `v` is not null-terminated or contains an interior null character,
+    /// this function returns [`FromVecWithNulError`] that holds `v`.
+    ///
+    /// No encoding checks are performed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WString;
+    /// let s = WString::from_vec_with_nul(vec![0x0074, 0x0000]).unwrap();
+    /// assert_eq!(s.as_bytes().len(), 1);
+    /// ```
+    pub fn from_vec_with_nul(
+        v: Vec<u16>,
+    ) -> Result<Self, FromVecWithNulError<u16>> {
+        match nul_position(&v) {
+            Some(i) if i + 1 == v.len() => unsafe {
+                Ok(Self::new_nul_unchecked(v))
+            },
+            Some(i) => Err(FromVecWithNulError {
+                error: FromBytesWithNulError::InteriorNul { position: i },
+                bytes: v,
+            }),
+            None => Err(FromVecWithNulError {
+                error: FromBytesWithNulError::NotNulTerminated {
+                    position: v.len(),
+                },
+                bytes: v,
+            }),
+        }
+    }
+
     /// Creates [`WString`] from [`Vec`]<u16> without any encoding checks.
     ///
     /// # Safety
@@ -115,11 +307,134 @@ impl WString {
 
     /// Creates [`WString`] from [`Vec`]<u8> without any encoding checks.
     ///
+    /// `v` is read as UTF-16LE. If the length of `v` is odd, the last byte
+    /// is treated as a code unit whose upper byte is 0.
+    ///
     /// # Safety
     ///
     /// `v` must be a correct Unicode string.
     pub unsafe fn new_c_unchecked<T: Into<Vec<u8>>>(v: T) -> Self {
-        unsafe { Self::_new2(v.into()) }
+        let mut v = v.into();
+        if v.len() & 1 == 1 {
+            v.push(0);
+        } // Make the length even.
+        Self::from_u16le_vec(v).expect("the length must be even")
+    }
+
+    /// Creates [`WString`] from UTF-16LE bytes.
+    ///
+    /// `b` is copied into a newly allocated buffer, so `b` does not need to
+    /// be aligned. The string ends at the first null character of `b`.
+    ///
+    /// Returns [`FromU16BytesError::OddLength`] if the length of `b` is odd.
+    /// Unpaired surrogates are not checked. Use
+    /// [`WString::from_u16le_bytes_strict`] to reject them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WString;
+    /// let s = WString::from_u16le_bytes(b"t\0e\0s\0t\0").unwrap();
+    /// assert_eq!(s.to_string_lossy(), "test");
+    /// assert!(WString::from_u16le_bytes(b"t\0e").is_err());
+    /// ```
+    #[inline]
+    pub fn from_u16le_bytes(b: &[u8]) -> Result<Self, FromU16BytesError> {
+        Self::from_utf16_bytes(b, ByteOrder::LittleEndian)
+    }
+
+    /// Creates [`WString`] from UTF-16BE bytes.
+    ///
+    /// This function is the same as [`WString::from_u16le_bytes`], except
+    /// that `b` is read as UTF-16BE. The string ends at the first null
+    /// character of `b`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WString;
+    /// let s = WString::from_utf16be_bytes(b"\0t\0e\0s\0t").unwrap();
+    /// assert_eq!(s.to_string_lossy(), "test");
+    /// ```
+    #[inline]
+    pub fn from_utf16be_bytes(b: &[u8]) -> Result<Self, FromU16BytesError> {
+        Self::from_utf16_bytes(b, ByteOrder::BigEndian)
+    }
+
+    /// Creates [`WString`] from UTF-16 bytes in `order`.
+    ///
+    /// The result does not depend on the byte order of the host. The string
+    /// ends at the first null character of `b`.
+    ///
+    /// Returns [`FromU16BytesError::OddLength`] if the length of `b` is odd.
+    pub fn from_utf16_bytes(
+        b: &[u8],
+        order: ByteOrder,
+    ) -> Result<Self, FromU16BytesError> {
+        if b.len() & 1 == 1 {
+            return Err(FromU16BytesError::OddLength { len: b.len() });
+        }
+        let v = b
+            .chunks_exact(2)
+            .map(|x| order.from_bytes([x[0], x[1]]))
+            .collect::<Vec<u16>>();
+        // Any sequence of u16 is accepted by Windows.
+        unsafe { Ok(Self::_new(v)) }
+    }
+
+    /// Creates [`WString`] from UTF-16 bytes that may start with a BOM.
+    ///
+    /// The byte order is detected from the BOM, and the BOM is removed.
+    /// If `b` does not start with a BOM, `b` is read as UTF-16LE, which is
+    /// the default of Windows. The string ends at the first null character
+    /// of `b`.
+    ///
+    /// Returns the string and the detected byte order.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{ByteOrder, WString};
+    /// let (s, order) =
+    ///     WString::from_utf16_bytes_with_bom(b"\xFE\xFF\0t\0e").unwrap();
+    /// assert_eq!(s.to_string_lossy(), "te");
+    /// assert_eq!(order, ByteOrder::BigEndian);
+    /// ```
+    pub fn from_utf16_bytes_with_bom(
+        b: &[u8],
+    ) -> Result<(Self, ByteOrder), FromU16BytesError> {
+        let (b, order) = match b {
+            [0xFF, 0xFE, rest @ ..] => (rest, ByteOrder::LittleEndian),
+            [0xFE, 0xFF, rest @ ..] => (rest, ByteOrder::BigEndian),
+            _ => (b, ByteOrder::LittleEndian),
+        };
+        Ok((Self::from_utf16_bytes(b, order)?, order))
+    }
+
+    /// Creates [`WString`] from UTF-16LE bytes.
+    ///
+    /// This function is the same as [`WString::from_u16le_bytes`], except
+    /// that it takes the ownership of `v`.
+    #[inline]
+    pub fn from_u16le_vec(v: Vec<u8>) -> Result<Self, FromU16BytesError> {
+        Self::from_u16le_bytes(&v)
+    }
+
+    /// Creates [`WString`] from UTF-16LE bytes with surrogate checks.
+    ///
+    /// Returns [`FromU16BytesError::UnpairedSurrogate`] if the string has an
+    /// unpaired surrogate, in addition to the errors of
+    /// [`WString::from_u16le_bytes`].
+    pub fn from_u16le_bytes_strict(
+        b: &[u8],
+    ) -> Result<Self, FromU16BytesError> {
+        let s = Self::from_u16le_bytes(b)?;
+        match unpaired_surrogate_position(s.as_bytes()) {
+            Some(position) => {
+                Err(FromU16BytesError::UnpairedSurrogate { position })
+            }
+            None => Ok(s),
+        }
     }
 
     #[inline]
@@ -136,22 +451,6 @@ impl WString {
         }
     }
 
-    #[inline]
-    pub(crate) unsafe fn _new2(mut v: Vec<u8>) -> Self {
-        if v.len() & 1 == 1 {
-            v.push(0);
-        } // Make the length even.
-        let v = v.leak();
-        unsafe {
-            let x = Vec::from_raw_parts(
-                v.as_ptr() as *mut u16,
-                v.len() / 2,
-                v.len() / 2,
-            );
-            Self::_new(x)
-        }
-    }
-
     /// Creates [`WString`] from `v` without a null-terminated check and any encoding checks.
     ///
     /// # Safety
@@ -245,6 +544,61 @@ impl WString {
             }
         }
     }
+
+    /// Consumes [`WString`] and transfers the ownership of the string to a
+    /// C caller.
+    ///
+    /// The pointer must be returned to Rust and freed by
+    /// [`WString::from_raw`]. It must not be freed by other deallocators such
+    /// as `free`, `LocalFree`, `HeapFree` or `CoTaskMemFree`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::WString;
+    /// let s = WString::from_str("test").unwrap();
+    /// let ptr = s.into_raw();
+    /// let s = unsafe { WString::from_raw(ptr) };
+    /// assert_eq!(s.to_string_lossy(), "test");
+    /// ```
+    ///
+    /// The string is cut at the first null character and terminated, so
+    /// that [`WString::from_raw`] recomputes the size of the allocation.
+    pub fn into_raw(self) -> *mut u16 {
+        Box::into_raw(into_nul_terminated(self.into_inner())) as *mut u16
+    }
+
+    /// Retakes the ownership of [`WString`] that was transferred to a C caller
+    /// by [`WString::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a pointer returned by [`WString::into_raw`]. The size of
+    /// the allocation is recomputed from the first null character, so the C
+    /// caller must not change the length of the string.
+    pub unsafe fn from_raw(ptr: *mut u16) -> Self {
+        unsafe {
+            let len = wcslen(ptr);
+            let slice = ptr::slice_from_raw_parts_mut(ptr, len + 1);
+            Self {
+                inner: Box::from_raw(slice),
+            }
+        }
+    }
+
+    /// Converts [`WString`] to [`Box`]<[`WStr`]> without copying.
+    #[inline]
+    pub fn into_boxed_wstr(self) -> Box<WStr> {
+        let raw = Box::into_raw(self.into_inner()) as *mut WStr;
+        unsafe { Box::from_raw(raw) }
+    }
+
+    /// Takes the buffer out of [`WString`] without running [`Drop`].
+    #[inline]
+    fn into_inner(self) -> Box<[wchar_t]> {
+        let this = mem::ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.inner) }
+    }
 }
 
 impl ops::Deref for WString {
@@ -312,12 +666,23 @@ impl AsRef<[u16]> for WString {
     fn as_ref(&self) -> &[u16] { self.as_bytes() }
 }
 
+impl From<Box<WStr>> for WString {
+    #[inline]
+    fn from(x: Box<WStr>) -> Self { x.into_wstring() }
+}
+
+impl From<WString> for Box<WStr> {
+    #[inline]
+    fn from(x: WString) -> Self { x.into_boxed_wstr() }
+}
+
 impl From<&WStr> for WString {
     fn from(x: &WStr) -> Self {
         unsafe { Self::new_nul_unchecked(x.to_bytes_with_nul().to_vec()) }
     }
 }
 
+#[cfg(windows)]
 impl TryFrom<&AStr> for WString {
     type Error = ConvertError;
 
@@ -325,6 +690,7 @@ impl TryFrom<&AStr> for WString {
     fn try_from(x: &AStr) -> Result<Self, Self::Error> { x.to_wstring() }
 }
 
+#[cfg(windows)]
 impl TryFrom<AString> for WString {
     type Error = ConvertError;
 
@@ -334,6 +700,7 @@ impl TryFrom<AString> for WString {
     }
 }
 
+#[cfg(windows)]
 impl TryFrom<&AString> for WString {
     type Error = ConvertError;
 
@@ -395,6 +762,67 @@ impl AString {
     #[inline]
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
+    /// Creates [`AString`] from `v`.
+    ///
+    /// A null terminator is appended to `v`. If `v` contains a null
+    /// character, this function returns [`NulError`] that holds its position
+    /// and `v`.
+    ///
+    /// No encoding checks are performed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::AString;
+    /// let s = AString::new(b"test".to_vec()).unwrap();
+    /// assert_eq!(s.as_bytes().len(), 4);
+    /// let e = AString::new(b"t\0t".to_vec()).unwrap_err();
+    /// assert_eq!(e.nul_position(), 1);
+    /// ```
+    pub fn new<T: Into<Vec<u8>>>(v: T) -> Result<Self, NulError<u8>> {
+        let mut v = v.into();
+        if let Some(i) = nul_position(&v) {
+            return Err(NulError(i, v));
+        }
+        v.reserve_exact(1);
+        v.push(0);
+        unsafe { Ok(Self::new_nul_unchecked(v)) }
+    }
+
+    /// Creates [`AString`] from `v` that ends with a null terminator.
+    ///
+    /// If `v` is not null-terminated or contains an interior null character,
+    /// this function returns [`FromVecWithNulError`] that holds `v`.
+    ///
+    /// No encoding checks are performed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::AString;
+    /// let s = AString::from_vec_with_nul(b"t\0".to_vec()).unwrap();
+    /// assert_eq!(s.as_bytes().len(), 1);
+    /// ```
+    pub fn from_vec_with_nul(
+        v: Vec<u8>,
+    ) -> Result<Self, FromVecWithNulError<u8>> {
+        match nul_position(&v) {
+            Some(i) if i + 1 == v.len() => unsafe {
+                Ok(Self::new_nul_unchecked(v))
+            },
+            Some(i) => Err(FromVecWithNulError {
+                error: FromBytesWithNulError::InteriorNul { position: i },
+                bytes: v,
+            }),
+            None => Err(FromVecWithNulError {
+                error: FromBytesWithNulError::NotNulTerminated {
+                    position: v.len(),
+                },
+                bytes: v,
+            }),
+        }
+    }
+
     /// Creates [`AString`] from `v` without any encoding checks.
     ///
     /// # Safety
@@ -435,6 +863,7 @@ impl AString {
     /// let s = AString::from_str("test").unwrap();
     /// println!("{:?}", s);
     /// ```
+    #[cfg(windows)]
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(x: &str) -> ConvertResult<Self> {
         // UTF-8 -> Unicode -> ANSI
@@ -450,6 +879,7 @@ impl AString {
     /// let s = AString::from_str_lossy("test🍣");
     /// println!("{:?}", s);
     /// ```
+    #[cfg(windows)]
     pub fn from_str_lossy(x: &str) -> Self {
         // UTF-8 -> Unicode -> ANSI
         WString::from_str_lossy(x).to_astring_lossy()
@@ -495,6 +925,61 @@ impl AString {
             }
         }
     }
+
+    /// Consumes [`AString`] and transfers the ownership of the string to a
+    /// C caller.
+    ///
+    /// The pointer must be returned to Rust and freed by
+    /// [`AString::from_raw`]. It must not be freed by other deallocators such
+    /// as `free`, `LocalFree`, `HeapFree` or `CoTaskMemFree`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::AString;
+    /// let s = AString::new(b"test".to_vec()).unwrap();
+    /// let ptr = s.into_raw();
+    /// let s = unsafe { AString::from_raw(ptr) };
+    /// assert_eq!(s.as_bytes(), b"test");
+    /// ```
+    ///
+    /// The string is cut at the first null character and terminated, so
+    /// that [`AString::from_raw`] recomputes the size of the allocation.
+    pub fn into_raw(self) -> *mut u8 {
+        Box::into_raw(into_nul_terminated(self.into_inner())) as *mut u8
+    }
+
+    /// Retakes the ownership of [`AString`] that was transferred to a C caller
+    /// by [`AString::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a pointer returned by [`AString::into_raw`]. The size of
+    /// the allocation is recomputed from the first null character, so the C
+    /// caller must not change the length of the string.
+    pub unsafe fn from_raw(ptr: *mut u8) -> Self {
+        unsafe {
+            let len = strlen(ptr);
+            let slice = ptr::slice_from_raw_parts_mut(ptr, len + 1);
+            Self {
+                inner: Box::from_raw(slice),
+            }
+        }
+    }
+
+    /// Converts [`AString`] to [`Box`]<[`AStr`]> without copying.
+    #[inline]
+    pub fn into_boxed_astr(self) -> Box<AStr> {
+        let raw = Box::into_raw(self.into_inner()) as *mut AStr;
+        unsafe { Box::from_raw(raw) }
+    }
+
+    /// Takes the buffer out of [`AString`] without running [`Drop`].
+    #[inline]
+    fn into_inner(self) -> Box<[u8]> {
+        let this = mem::ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.inner) }
+    }
 }
 
 impl ops::Deref for AString {
@@ -520,12 +1005,23 @@ impl Drop for AString {
     }
 }
 
+impl From<Box<AStr>> for AString {
+    #[inline]
+    fn from(x: Box<AStr>) -> Self { x.into_astring() }
+}
+
+impl From<AString> for Box<AStr> {
+    #[inline]
+    fn from(x: AString) -> Self { x.into_boxed_astr() }
+}
+
 impl From<&AStr> for AString {
     fn from(x: &AStr) -> Self {
         unsafe { Self::new_nul_unchecked(x.to_bytes_with_nul().to_vec()) }
     }
 }
 
+#[cfg(windows)]
 impl TryInto<String> for AString {
     type Error = ConvertError;
 
@@ -533,6 +1029,7 @@ impl TryInto<String> for AString {
     fn try_into(self) -> Result<String, Self::Error> { self.try_to_string() }
 }
 
+#[cfg(windows)]
 impl TryFrom<&str> for AString {
     type Error = ConvertError;
 
@@ -550,6 +1047,7 @@ impl AsRef<[u8]> for AString {
     fn as_ref(&self) -> &[u8] { self.as_bytes() }
 }
 
+#[cfg(windows)]
 impl TryFrom<String> for AString {
     type Error = ConvertError;
 
@@ -559,6 +1057,7 @@ impl TryFrom<String> for AString {
     }
 }
 
+#[cfg(windows)]
 impl TryFrom<&String> for AString {
     type Error = ConvertError;
 
@@ -568,6 +1067,7 @@ impl TryFrom<&String> for AString {
     }
 }
 
+#[cfg(windows)]
 impl TryFrom<&WStr> for AString {
     type Error = ConvertError;
 
@@ -575,6 +1075,7 @@ impl TryFrom<&WStr> for AString {
     fn try_from(x: &WStr) -> Result<Self, Self::Error> { x.to_astring() }
 }
 
+#[cfg(windows)]
 impl TryFrom<WString> for AString {
     type Error = ConvertError;
 
@@ -584,6 +1085,7 @@ impl TryFrom<WString> for AString {
     }
 }
 
+#[cfg(windows)]
 impl TryFrom<&WString> for AString {
     type Error = ConvertError;
 
@@ -602,5 +1104,91 @@ impl ops::Add<&AStr> for AString {
     }
 }
 
+/// Implements the std ownership traits for a borrowed string and its owned
+/// string.
+macro_rules! str_impl_owned {
+    ($s:ident, $o:ident, $t:ty) => {
+        impl Borrow<$s> for $o {
+            #[inline]
+            fn borrow(&self) -> &$s { self }
+        }
+
+        impl ToOwned for $s {
+            type Owned = $o;
+
+            #[inline]
+            fn to_owned(&self) -> $o { $o::from(self) }
+        }
+
+        impl Default for $o {
+            #[inline]
+            fn default() -> Self { <&$s>::default().to_owned() }
+        }
+
+        impl From<&$s> for Box<$s> {
+            fn from(x: &$s) -> Self {
+                let b: Box<[$t]> = x.to_bytes_with_nul().into();
+                unsafe { Box::from_raw(Box::into_raw(b) as *mut $s) }
+            }
+        }
+
+        impl Default for Box<$s> {
+            #[inline]
+            fn default() -> Self { Box::from(<&$s>::default()) }
+        }
+
+        impl Clone for Box<$s> {
+            #[inline]
+            fn clone(&self) -> Self { Box::from(&**self) }
+        }
+
+        impl From<&$s> for Rc<$s> {
+            fn from(x: &$s) -> Self {
+                let rc: Rc<[$t]> = Rc::from(x.to_bytes_with_nul());
+                unsafe { Rc::from_raw(Rc::into_raw(rc) as *const $s) }
+            }
+        }
+
+        impl From<$o> for Rc<$s> {
+            #[inline]
+            fn from(x: $o) -> Self { Rc::from(&*x) }
+        }
+
+        impl From<&$s> for Arc<$s> {
+            fn from(x: &$s) -> Self {
+                let arc: Arc<[$t]> = Arc::from(x.to_bytes_with_nul());
+                unsafe { Arc::from_raw(Arc::into_raw(arc) as *const $s) }
+            }
+        }
+
+        impl From<$o> for Arc<$s> {
+            #[inline]
+            fn from(x: $o) -> Self { Arc::from(&*x) }
+        }
+
+        impl<'a> From<&'a $s> for Cow<'a, $s> {
+            #[inline]
+            fn from(x: &'a $s) -> Self { Cow::Borrowed(x) }
+        }
+
+        impl<'a> From<&'a $o> for Cow<'a, $s> {
+            #[inline]
+            fn from(x: &'a $o) -> Self { Cow::Borrowed(x) }
+        }
+
+        impl From<$o> for Cow<'_, $s> {
+            #[inline]
+            fn from(x: $o) -> Self { Cow::Owned(x) }
+        }
+
+        impl From<Cow<'_, $s>> for $o {
+            #[inline]
+            fn from(x: Cow<'_, $s>) -> Self { x.into_owned() }
+        }
+    };
+}
+
 str_impl_debug!(WString);
-str_impl_debug!(AString);
+str_impl_debug!(AString, all(windows, feature = "std"));
+str_impl_owned!(WStr, WString, u16);
+str_impl_owned!(AStr, AString, u8);
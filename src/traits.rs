@@ -12,6 +12,7 @@ pub trait ToWString {
     fn to_wstring_lossy(&self) -> WString;
 }
 
+#[cfg(windows)]
 pub trait ToAString {
     fn to_astring(&self) -> AString {
         self.try_to_astring().expect("Failed to convert to AString")
@@ -36,6 +37,7 @@ macro_rules! impl_to_wstring {
     };
 }
 
+#[cfg(windows)]
 macro_rules! impl_to_astring {
     ($x:ident) => {
         impl ToAString for $x {
@@ -50,7 +52,9 @@ macro_rules! impl_to_astring {
     };
 }
 
+#[cfg(windows)]
 impl_to_astring!(String);
 impl_to_wstring!(String);
+#[cfg(windows)]
 impl_to_astring!(str);
 impl_to_wstring!(str);
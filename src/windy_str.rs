@@ -1,23 +1,33 @@
 // Copyright takubokudori.
 // This source code is licensed under the MIT or Apache-2.0 license.
 use crate::{
-    __lib::{cmp::Ordering, fmt::Write, slice},
+    __lib::{
+        cmp::Ordering,
+        fmt::Write,
+        hash::{Hash, Hasher},
+        slice,
+    },
     *,
 };
 
 #[cfg(feature = "std")]
 use crate::convert::*;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
 
 macro_rules! str_impl_debug {
     ($x:ident) => {
+        str_impl_debug!($x, feature = "std");
+    };
+    ($x:ident, $lossy:meta) => {
         impl fmt::Debug for $x {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_char('"')?;
-                #[cfg(not(feature = "std"))]
+                #[cfg(not($lossy))]
                 {
                     fmt::Debug::fmt(&self.to_bytes_with_nul(), f)?;
                 }
-                #[cfg(feature = "std")]
+                #[cfg($lossy)]
                 {
                     fmt::Display::fmt(&self.to_string_lossy(), f)?;
                 }
@@ -27,6 +37,114 @@ macro_rules! str_impl_debug {
     };
 }
 
+/// An error indicating that a null character was not in the expected position.
+///
+/// This error is returned by [`WStr::from_bytes_with_nul`] and
+/// [`AStr::from_bytes_with_nul`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FromBytesWithNulError {
+    /// The slice has a null character at `position` before its end.
+    InteriorNul { position: usize },
+    /// The slice is not null-terminated.
+    ///
+    /// `position` is the length of the slice, where a null character is
+    /// expected.
+    NotNulTerminated { position: usize },
+}
+
+impl FromBytesWithNulError {
+    /// Returns the position of the interior or missing null character.
+    #[inline]
+    pub fn nul_position(&self) -> usize {
+        match self {
+            Self::InteriorNul { position } => *position,
+            Self::NotNulTerminated { position } => *position,
+        }
+    }
+}
+
+impl fmt::Display for FromBytesWithNulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InteriorNul { position } => write!(
+                f,
+                "data provided contains an interior nul at position {}",
+                position
+            ),
+            Self::NotNulTerminated { position } => write!(
+                f,
+                "data provided is not nul terminated at position {}",
+                position
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromBytesWithNulError {}
+
+/// An error indicating that no null character was present.
+///
+/// This error is returned by [`WStr::from_bytes_until_nul`] and
+/// [`AStr::from_bytes_until_nul`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FromBytesUntilNulError(usize);
+
+impl FromBytesUntilNulError {
+    /// Returns the length of the slice, where a null character is expected.
+    #[inline]
+    pub fn nul_position(&self) -> usize { self.0 }
+}
+
+impl fmt::Display for FromBytesUntilNulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "data provided does not contain a nul at position {}",
+            self.0
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromBytesUntilNulError {}
+
+/// Byte order of UTF-16 code units.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ByteOrder {
+    /// Little-endian, for example UTF-16LE.
+    LittleEndian,
+    /// Big-endian, for example UTF-16BE.
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// The byte order of the host.
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::LittleEndian;
+    /// The byte order of the host.
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::BigEndian;
+
+    /// Converts `x` to bytes in this byte order.
+    #[inline]
+    pub fn to_bytes(self, x: u16) -> [u8; 2] {
+        match self {
+            Self::LittleEndian => x.to_le_bytes(),
+            Self::BigEndian => x.to_be_bytes(),
+        }
+    }
+
+    /// Converts bytes in this byte order to `u16`.
+    #[inline]
+    pub fn from_bytes(self, x: [u8; 2]) -> u16 {
+        match self {
+            Self::LittleEndian => u16::from_le_bytes(x),
+            Self::BigEndian => u16::from_be_bytes(x),
+        }
+    }
+}
+
 /// Represents a borrowed Unicode string.
 #[repr(C)]
 pub struct WStr {
@@ -35,21 +153,21 @@ pub struct WStr {
 
 impl WStr {
     #[inline]
-    pub fn as_ptr(&self) -> *const wchar_t { self.inner.as_ptr() }
+    pub const fn as_ptr(&self) -> *const wchar_t { self.inner.as_ptr() }
 
     #[inline]
     pub fn as_mut_ptr(&mut self) -> *mut wchar_t { self.inner.as_mut_ptr() }
 
     /// Returns the length of bytes.
     #[inline]
-    pub fn len(&self) -> usize { size_of_val(&self.inner) }
+    pub const fn len(&self) -> usize { size_of_val(&self.inner) }
 
     /// Returns `true` if the length of bytes is 0.
     #[inline]
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
     #[inline]
-    pub fn to_bytes_with_nul(&self) -> &[u16] { &self.inner }
+    pub const fn to_bytes_with_nul(&self) -> &[u16] { &self.inner }
 
     pub fn to_bytes(&self) -> &[u16] {
         let bytes = self.to_bytes_with_nul();
@@ -66,11 +184,68 @@ impl WStr {
         }
     }
 
+    /// Returns the bytes of the string in the byte order of the host.
+    ///
+    /// Use [`WStr::to_u8_bytes_in`] to get the bytes in a specific byte
+    /// order.
     pub fn to_u8_bytes(&self) -> &[u8] {
         let bytes = self.to_u8_bytes_with_nul();
         &bytes[..bytes.len() - 2]
     }
 
+    /// Returns the bytes of the string with a null terminator in `order`.
+    ///
+    /// The bytes are borrowed if `order` is the byte order of the host.
+    #[cfg(feature = "std")]
+    pub fn to_u8_bytes_with_nul_in(&self, order: ByteOrder) -> Cow<'_, [u8]> {
+        if order == ByteOrder::NATIVE {
+            return Cow::Borrowed(self.to_u8_bytes_with_nul());
+        }
+        Cow::Owned(
+            self.to_bytes_with_nul()
+                .iter()
+                .flat_map(|&x| order.to_bytes(x))
+                .collect(),
+        )
+    }
+
+    /// Returns the bytes of the string in `order`.
+    ///
+    /// The bytes are borrowed if `order` is the byte order of the host.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{ByteOrder, wstr};
+    /// let s = wstr!("ab");
+    /// assert_eq!(&*s.to_u8_bytes_in(ByteOrder::BigEndian), b"\0a\0b");
+    /// assert_eq!(&*s.to_u8_bytes_in(ByteOrder::LittleEndian), b"a\0b\0");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_u8_bytes_in(&self, order: ByteOrder) -> Cow<'_, [u8]> {
+        match self.to_u8_bytes_with_nul_in(order) {
+            Cow::Borrowed(x) => Cow::Borrowed(&x[..x.len() - 2]),
+            Cow::Owned(mut x) => {
+                x.truncate(x.len() - 2);
+                Cow::Owned(x)
+            }
+        }
+    }
+
+    /// Returns the UTF-16LE bytes of the string without a null terminator.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_utf16le_bytes(&self) -> Vec<u8> {
+        self.to_u8_bytes_in(ByteOrder::LittleEndian).into_owned()
+    }
+
+    /// Returns the UTF-16BE bytes of the string without a null terminator.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_utf16be_bytes(&self) -> Vec<u8> {
+        self.to_u8_bytes_in(ByteOrder::BigEndian).into_owned()
+    }
+
     #[cfg(feature = "std")]
     /// Converts [`WString`] to UTF-8 string.
     ///
@@ -106,7 +281,7 @@ impl WStr {
         unsafe { WString::new_nul_unchecked(&self.inner) }
     }
 
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     /// Converts [`WStr`] to [`AString`].
     ///
     /// # Example
@@ -124,7 +299,7 @@ impl WStr {
         unsafe { Ok(AString::new_unchecked(mb)) }
     }
 
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     /// Converts [`WStr`] to [`AString`].
     ///
     /// # Example
@@ -141,13 +316,80 @@ impl WStr {
         unsafe { AString::new_unchecked(mb) }
     }
 
+    /// Converts [`Box`]<[`WStr`]> to [`WString`] without copying.
+    #[cfg(feature = "std")]
+    pub fn into_wstring(self: Box<Self>) -> WString {
+        let raw = Box::into_raw(self) as *mut [u16];
+        unsafe { WString::new_nul_unchecked(Box::from_raw(raw)) }
+    }
+
+    /// Creates a new `&WStr` from `bytes`.
+    ///
+    /// `bytes` must be null-terminated and must not contain any interior null
+    /// characters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{FromBytesWithNulError, WStr};
+    ///
+    /// let s =
+    ///     WStr::from_bytes_with_nul(&[0x0074, 0x0065, 0x0073, 0x0074, 0x0000])
+    ///         .unwrap();
+    /// assert_eq!(s.to_bytes().len(), 4);
+    /// assert_eq!(
+    ///     WStr::from_bytes_with_nul(&[0x74, 0x00, 0x74, 0x00]),
+    ///     Err(FromBytesWithNulError::InteriorNul { position: 1 })
+    /// );
+    /// ```
+    pub const fn from_bytes_with_nul(
+        bytes: &[u16],
+    ) -> Result<&Self, FromBytesWithNulError> {
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == 0 {
+                if i + 1 == bytes.len() {
+                    // null-terminated.
+                    return Ok(unsafe {
+                        Self::from_bytes_with_nul_unchecked(bytes)
+                    });
+                }
+                return Err(FromBytesWithNulError::InteriorNul { position: i });
+            }
+            i += 1;
+        }
+        Err(FromBytesWithNulError::NotNulTerminated {
+            position: bytes.len(),
+        })
+    }
+
+    /// Creates a new `&WStr` from `bytes` up to and including the first null
+    /// character.
+    ///
+    /// The rest of `bytes` after the first null character is ignored.
+    pub const fn from_bytes_until_nul(
+        bytes: &[u16],
+    ) -> Result<&Self, FromBytesUntilNulError> {
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == 0 {
+                let (bytes, _) = bytes.split_at(i + 1);
+                return Ok(unsafe {
+                    Self::from_bytes_with_nul_unchecked(bytes)
+                });
+            }
+            i += 1;
+        }
+        Err(FromBytesUntilNulError(bytes.len()))
+    }
+
     /// Creates a new `&WStr` from `bytes`.
     ///
     /// # Safety
     ///
     /// `bytes` must be a correct Unicode string.
     #[inline]
-    pub unsafe fn from_bytes_with_nul_unchecked(bytes: &[u16]) -> &Self {
+    pub const unsafe fn from_bytes_with_nul_unchecked(bytes: &[u16]) -> &Self {
         unsafe { &*(bytes as *const [u16] as *const Self) }
     }
 
@@ -219,6 +461,26 @@ impl PartialOrd for WStr {
     }
 }
 
+impl Hash for WStr {
+    // Consistent with the hash of `WString`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes_with_nul().hash(state)
+    }
+}
+
+impl AsRef<WStr> for WStr {
+    #[inline]
+    fn as_ref(&self) -> &WStr { self }
+}
+
+impl Default for &WStr {
+    #[inline]
+    fn default() -> Self {
+        const EMPTY: &[u16] = &[0];
+        unsafe { WStr::from_bytes_with_nul_unchecked(EMPTY) }
+    }
+}
+
 impl Ord for WStr {
     fn cmp(&self, other: &Self) -> Ordering {
         self.to_bytes().cmp(other.to_bytes())
@@ -233,7 +495,7 @@ pub struct AStr {
 
 impl AStr {
     #[inline]
-    pub fn as_ptr(&self) -> *const i8 { self.inner.as_ptr() as *const i8 }
+    pub const fn as_ptr(&self) -> *const i8 { self.inner.as_ptr() as *const i8 }
 
     #[inline]
     pub fn as_mut_ptr(&mut self) -> *mut i8 {
@@ -248,14 +510,14 @@ impl AStr {
 
     /// Returns the length of bytes.
     #[inline]
-    pub fn len(&self) -> usize { self.inner.len() }
+    pub const fn len(&self) -> usize { self.inner.len() }
 
     /// Returns `true` if the length of bytes is 0.
     #[inline]
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
     #[inline]
-    pub fn to_bytes_with_nul(&self) -> &[u8] { &self.inner }
+    pub const fn to_bytes_with_nul(&self) -> &[u8] { &self.inner }
 
     #[inline]
     pub fn to_bytes(&self) -> &[u8] {
@@ -264,14 +526,14 @@ impl AStr {
     }
 
     /// Creates [`String`] from [`AStr`].
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     pub fn try_to_string(&self) -> ConvertResult<String> {
         // ANSI -> Unicode -> UTF-8
         self.to_wstring()?.try_to_string()
     }
 
     /// Creates [`String`] from [`AStr`].
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     pub fn to_string_lossy(&self) -> String {
         // ANSI -> Unicode -> UTF-8
         self.to_wstring_lossy().to_string_lossy()
@@ -295,7 +557,7 @@ impl AStr {
     /// let s2 = AString::from_str("test").unwrap().to_wstring().unwrap();
     /// assert_eq!(s, s2);
     /// ```
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     pub fn to_wstring(&self) -> ConvertResult<WString> {
         let wc = mb_to_wide(self.to_bytes()).map_err(conv_err!(@unicode))?;
         // valid Unicode string
@@ -312,7 +574,7 @@ impl AStr {
     /// let s2 = AString::from_str("test").unwrap().to_wstring_lossy();
     /// assert_eq!(s, s2);
     /// ```
-    #[cfg(feature = "std")]
+    #[cfg(all(windows, feature = "std"))]
     pub fn to_wstring_lossy(&self) -> WString {
         let wc = mb_to_wide_lossy(self.to_bytes())
             .map_err(conv_err!(@unicode))
@@ -321,13 +583,78 @@ impl AStr {
         unsafe { WString::_new(wc) }
     }
 
+    /// Converts [`Box`]<[`AStr`]> to [`AString`] without copying.
+    #[cfg(feature = "std")]
+    pub fn into_astring(self: Box<Self>) -> AString {
+        let raw = Box::into_raw(self) as *mut [u8];
+        unsafe { AString::new_nul_unchecked(Box::from_raw(raw)) }
+    }
+
+    /// Creates a new `&AStr` from `bytes`.
+    ///
+    /// `bytes` must be null-terminated and must not contain any interior null
+    /// characters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use windy::{AStr, FromBytesWithNulError};
+    ///
+    /// let s = AStr::from_bytes_with_nul(b"test\0").unwrap();
+    /// assert_eq!(s.to_bytes().len(), 4);
+    /// assert_eq!(
+    ///     AStr::from_bytes_with_nul(&[0x74, 0x00, 0x74, 0x00]),
+    ///     Err(FromBytesWithNulError::InteriorNul { position: 1 })
+    /// );
+    /// ```
+    pub const fn from_bytes_with_nul(
+        bytes: &[u8],
+    ) -> Result<&Self, FromBytesWithNulError> {
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == 0 {
+                if i + 1 == bytes.len() {
+                    // null-terminated.
+                    return Ok(unsafe {
+                        Self::from_bytes_with_nul_unchecked(bytes)
+                    });
+                }
+                return Err(FromBytesWithNulError::InteriorNul { position: i });
+            }
+            i += 1;
+        }
+        Err(FromBytesWithNulError::NotNulTerminated {
+            position: bytes.len(),
+        })
+    }
+
+    /// Creates a new `&AStr` from `bytes` up to and including the first null
+    /// character.
+    ///
+    /// The rest of `bytes` after the first null character is ignored.
+    pub const fn from_bytes_until_nul(
+        bytes: &[u8],
+    ) -> Result<&Self, FromBytesUntilNulError> {
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == 0 {
+                let (bytes, _) = bytes.split_at(i + 1);
+                return Ok(unsafe {
+                    Self::from_bytes_with_nul_unchecked(bytes)
+                });
+            }
+            i += 1;
+        }
+        Err(FromBytesUntilNulError(bytes.len()))
+    }
+
     /// Creates a new `&AStr` from `bytes`.
     ///
     /// # Safety
     ///
     /// `bytes` must be a correct ANSI string.
     #[inline]
-    pub unsafe fn from_bytes_with_nul_unchecked(bytes: &[u8]) -> &Self {
+    pub const unsafe fn from_bytes_with_nul_unchecked(bytes: &[u8]) -> &Self {
         unsafe { &*(bytes as *const [u8] as *const Self) }
     }
 
@@ -395,6 +722,26 @@ impl PartialOrd for AStr {
     }
 }
 
+impl Hash for AStr {
+    // Consistent with the hash of `AString`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes_with_nul().hash(state)
+    }
+}
+
+impl AsRef<AStr> for AStr {
+    #[inline]
+    fn as_ref(&self) -> &AStr { self }
+}
+
+impl Default for &AStr {
+    #[inline]
+    fn default() -> Self {
+        const EMPTY: &[u8] = &[0];
+        unsafe { AStr::from_bytes_with_nul_unchecked(EMPTY) }
+    }
+}
+
 impl Ord for AStr {
     fn cmp(&self, other: &Self) -> Ordering {
         self.to_bytes().cmp(other.to_bytes())
@@ -402,4 +749,4 @@ impl Ord for AStr {
 }
 
 str_impl_debug!(WStr);
-str_impl_debug!(AStr);
+str_impl_debug!(AStr, all(windows, feature = "std"));
@@ -0,0 +1,539 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+//! WTF-8 strings.
+//!
+//! [WTF-8](https://simonsapin.github.io/wtf-8/) is a superset of UTF-8 that
+//! can represent unpaired surrogates of a Unicode string.
+//! A [`WStr`] is converted to WTF-8 and back without loss, and a WTF-8 string
+//! that does not contain unpaired surrogates is valid UTF-8.
+use crate::{
+    __lib::{fmt::Write, mem, slice},
+    *,
+};
+#[cfg(feature = "std")]
+use std::{
+    borrow::{Borrow, Cow},
+    ops,
+};
+
+/// An error indicating that a byte sequence is not valid WTF-8.
+///
+/// This error is returned by [`Wtf8::from_bytes`] and
+/// [`Wtf8Buf::from_bytes`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Wtf8Error {
+    valid_up_to: usize,
+}
+
+impl Wtf8Error {
+    /// Returns the length of the valid prefix in bytes.
+    #[inline]
+    pub fn valid_up_to(&self) -> usize { self.valid_up_to }
+}
+
+impl fmt::Display for Wtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid WTF-8 sequence found at position: {}",
+            self.valid_up_to
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Wtf8Error {}
+
+#[inline]
+fn is_cont(x: u8) -> bool { x & 0xC0 == 0x80 }
+
+/// Decodes a generalized UTF-8 sequence at the beginning of `b` and returns
+/// the code point and its length in bytes.
+///
+/// Returns `None` if the sequence is invalid.
+fn decode(b: &[u8]) -> Option<(u32, usize)> {
+    let x = *b.first()? as u32;
+    let (n, lo, hi) = match x {
+        0x00..=0x7F => return Some((x, 1)),
+        0xC2..=0xDF => (2, 0x80, 0xBF),
+        0xE0 => (3, 0xA0, 0xBF),
+        0xE1..=0xEF => (3, 0x80, 0xBF),
+        0xF0 => (4, 0x90, 0xBF),
+        0xF1..=0xF3 => (4, 0x80, 0xBF),
+        0xF4 => (4, 0x80, 0x8F),
+        _ => return None,
+    };
+    let b = b.get(..n)?;
+    if !(lo..=hi).contains(&b[1]) || !b[2..].iter().all(|&x| is_cont(x)) {
+        return None;
+    }
+    let cp = b[1..]
+        .iter()
+        .fold(x & (0x7F >> n), |cp, &x| cp << 6 | (x as u32 & 0x3F));
+    Some((cp, n))
+}
+
+/// Encodes `cp` to generalized UTF-8 and returns the number of bytes.
+#[cfg(feature = "std")]
+fn encode(cp: u32, buf: &mut [u8; 4]) -> usize {
+    if cp < 0x80 {
+        buf[0] = cp as u8;
+        1
+    } else if cp < 0x800 {
+        buf[0] = 0xC0 | (cp >> 6) as u8;
+        buf[1] = 0x80 | (cp & 0x3F) as u8;
+        2
+    } else if cp < 0x10000 {
+        buf[0] = 0xE0 | (cp >> 12) as u8;
+        buf[1] = 0x80 | (cp >> 6 & 0x3F) as u8;
+        buf[2] = 0x80 | (cp & 0x3F) as u8;
+        3
+    } else {
+        buf[0] = 0xF0 | (cp >> 18) as u8;
+        buf[1] = 0x80 | (cp >> 12 & 0x3F) as u8;
+        buf[2] = 0x80 | (cp >> 6 & 0x3F) as u8;
+        buf[3] = 0x80 | (cp & 0x3F) as u8;
+        4
+    }
+}
+
+/// Returns `true` if `b` starts with an encoded low surrogate.
+#[inline]
+fn starts_with_low_surrogate(b: &[u8]) -> bool {
+    matches!(b, [0xED, 0xB0..=0xBF, _, ..])
+}
+
+/// Returns `true` if `b` ends with an encoded high surrogate.
+#[inline]
+fn ends_with_high_surrogate(b: &[u8]) -> bool {
+    matches!(b, [.., 0xED, 0xA0..=0xAF, _])
+}
+
+/// Validates `b` as WTF-8.
+fn validate(b: &[u8]) -> Result<(), Wtf8Error> {
+    let mut i = 0;
+    while i < b.len() {
+        let (_, n) = decode(&b[i..]).ok_or(Wtf8Error { valid_up_to: i })?;
+        i += n;
+        // A surrogate pair must be encoded as a supplementary character.
+        if ends_with_high_surrogate(&b[..i])
+            && starts_with_low_surrogate(&b[i..])
+        {
+            return Err(Wtf8Error { valid_up_to: i - n });
+        }
+    }
+    Ok(())
+}
+
+/// Represents a borrowed WTF-8 string.
+///
+/// Unlike [`WStr`], [`Wtf8`] is not null-terminated.
+#[repr(transparent)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wtf8 {
+    bytes: [u8],
+}
+
+impl Wtf8 {
+    /// Creates [`Wtf8`] from `bytes`.
+    ///
+    /// If `bytes` is not valid WTF-8, this function returns [`Wtf8Error`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<&Self, Wtf8Error> {
+        validate(bytes)?;
+        Ok(unsafe { Self::from_bytes_unchecked(bytes) })
+    }
+
+    /// Creates [`Wtf8`] from `bytes` without any encoding checks.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid WTF-8.
+    #[inline]
+    pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> &Self {
+        unsafe { mem::transmute(bytes) }
+    }
+
+    /// Creates [`Wtf8`] from `s`.
+    ///
+    /// UTF-8 is always valid WTF-8.
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn from_str(s: &str) -> &Self {
+        unsafe { Self::from_bytes_unchecked(s.as_bytes()) }
+    }
+
+    /// Returns the bytes of the string.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] { &self.bytes }
+
+    /// Returns the length in bytes.
+    #[inline]
+    pub fn len(&self) -> usize { self.bytes.len() }
+
+    /// Returns `true` if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.bytes.is_empty() }
+
+    /// Returns `&str` if the string does not contain unpaired surrogates.
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.bytes).ok()
+    }
+
+    /// Returns an iterator over code points.
+    ///
+    /// An unpaired surrogate is returned as is.
+    #[inline]
+    pub fn code_points(&self) -> Wtf8CodePoints<'_> {
+        Wtf8CodePoints {
+            rest: self.bytes.iter(),
+        }
+    }
+
+    /// Returns an iterator over UTF-16 code units of the string.
+    #[inline]
+    pub fn encode_wide(&self) -> EncodeWide<'_> {
+        EncodeWide {
+            code_points: self.code_points(),
+            low: None,
+        }
+    }
+
+    /// Converts [`Wtf8`] to UTF-8 string.
+    ///
+    /// Unpaired surrogates are replaced with `\u{FFFD}`.
+    #[cfg(feature = "std")]
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self.as_str() {
+            Some(x) => Cow::Borrowed(x),
+            None => Cow::Owned(
+                self.code_points()
+                    .map(|x| {
+                        char::from_u32(x).unwrap_or(char::REPLACEMENT_CHARACTER)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts [`Wtf8`] to [`WString`].
+    ///
+    /// If the string contains a null character, this function returns
+    /// [`NulError`].
+    #[cfg(feature = "std")]
+    pub fn to_wstring(&self) -> Result<WString, NulError<u16>> {
+        WString::new(self.encode_wide().collect::<Vec<u16>>())
+    }
+}
+
+impl fmt::Debug for Wtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for x in self.code_points() {
+            match char::from_u32(x) {
+                Some(c) => {
+                    for c in c.escape_debug() {
+                        f.write_char(c)?;
+                    }
+                }
+                None => write!(f, "\\u{{{:X}}}", x)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+impl fmt::Display for Wtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for x in self.code_points() {
+            f.write_char(
+                char::from_u32(x).unwrap_or(char::REPLACEMENT_CHARACTER),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<[u8]> for Wtf8 {
+    #[inline]
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl AsRef<Wtf8> for str {
+    #[inline]
+    fn as_ref(&self) -> &Wtf8 { Wtf8::from_str(self) }
+}
+
+impl<'a> From<&'a str> for &'a Wtf8 {
+    #[inline]
+    fn from(x: &'a str) -> Self { Wtf8::from_str(x) }
+}
+
+/// An iterator over code points of [`Wtf8`].
+///
+/// This struct is created by [`Wtf8::code_points`].
+#[derive(Clone, Debug)]
+pub struct Wtf8CodePoints<'a> {
+    rest: slice::Iter<'a, u8>,
+}
+
+impl Iterator for Wtf8CodePoints<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest.as_slice();
+        // The string is valid WTF-8.
+        let (cp, n) = decode(rest)?;
+        self.rest = rest[n..].iter();
+        Some(cp)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len();
+        (n.div_ceil(4), Some(n))
+    }
+}
+
+/// An iterator over UTF-16 code units of [`Wtf8`].
+///
+/// This struct is created by [`Wtf8::encode_wide`].
+#[derive(Clone, Debug)]
+pub struct EncodeWide<'a> {
+    code_points: Wtf8CodePoints<'a>,
+    low: Option<u16>,
+}
+
+impl Iterator for EncodeWide<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(x) = self.low.take() {
+            return Some(x);
+        }
+        let cp = self.code_points.next()?;
+        if cp < 0x10000 {
+            return Some(cp as u16);
+        }
+        let cp = cp - 0x10000;
+        self.low = Some(0xDC00 | (cp & 0x3FF) as u16);
+        Some(0xD800 | (cp >> 10) as u16)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.code_points.size_hint();
+        let n = self.low.is_some() as usize;
+        (lo + n, hi.map(|x| x + n))
+    }
+}
+
+/// Represents an owned WTF-8 string.
+///
+/// # Example
+///
+/// ```no_run
+/// use windy::{WStr, Wtf8Buf};
+/// // "a" and an unpaired surrogate.
+/// let w = WStr::from_bytes_with_nul(&[0x61, 0xD800, 0x00]).unwrap();
+/// let s = Wtf8Buf::from_wstr(w);
+/// assert_eq!(s.as_bytes(), b"a\xED\xA0\x80");
+/// assert_eq!(&*s.to_wstring().unwrap(), w);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wtf8Buf {
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl Wtf8Buf {
+    /// Creates an empty [`Wtf8Buf`].
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Creates [`Wtf8Buf`] from `bytes`.
+    ///
+    /// If `bytes` is not valid WTF-8, this function returns [`Wtf8Error`].
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Wtf8Error> {
+        validate(&bytes)?;
+        Ok(Self { bytes })
+    }
+
+    /// Creates [`Wtf8Buf`] from `bytes` without any encoding checks.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid WTF-8.
+    #[inline]
+    pub unsafe fn from_bytes_unchecked(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    /// Creates [`Wtf8Buf`] from `s`.
+    #[inline]
+    pub fn from_string(s: String) -> Self {
+        Self {
+            bytes: s.into_bytes(),
+        }
+    }
+
+    /// Converts `s` to [`Wtf8Buf`] without loss.
+    pub fn from_wstr(s: &WStr) -> Self { Self::from_wide(s.to_bytes()) }
+
+    /// Converts UTF-16 code units to [`Wtf8Buf`] without loss.
+    pub fn from_wide(v: &[u16]) -> Self {
+        let mut ret = Self {
+            bytes: Vec::with_capacity(v.len()),
+        };
+        for x in char::decode_utf16(v.iter().copied()) {
+            match x {
+                Ok(c) => ret.push_char(c),
+                Err(e) => ret.push_code_point(e.unpaired_surrogate() as u32),
+            }
+        }
+        ret
+    }
+
+    /// Returns &[`Wtf8`].
+    #[inline]
+    pub fn as_wtf8(&self) -> &Wtf8 { self }
+
+    /// Converts [`Wtf8Buf`] to the bytes.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> { self.bytes }
+
+    /// Converts [`Wtf8Buf`] to [`String`].
+    ///
+    /// If the string contains unpaired surrogates, this function returns
+    /// `self`.
+    pub fn into_string(self) -> Result<String, Self> {
+        match String::from_utf8(self.bytes) {
+            Ok(x) => Ok(x),
+            Err(e) => Err(Self {
+                bytes: e.into_bytes(),
+            }),
+        }
+    }
+
+    /// Appends `c`.
+    #[inline]
+    pub fn push_char(&mut self, c: char) {
+        self.bytes
+            .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    /// Appends `s`.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) { self.push_wtf8(Wtf8::from_str(s)) }
+
+    /// Appends `s`.
+    ///
+    /// If the string ends with a high surrogate and `s` starts with a low
+    /// surrogate, they are joined into a supplementary character.
+    pub fn push_wtf8(&mut self, s: &Wtf8) {
+        let s = s.as_bytes();
+        if ends_with_high_surrogate(&self.bytes) && starts_with_low_surrogate(s)
+        {
+            let len = self.bytes.len() - 3;
+            let (hi, _) = decode(&self.bytes[len..]).unwrap();
+            let (lo, _) = decode(s).unwrap();
+            self.bytes.truncate(len);
+            self.push_code_point(0x10000 + ((hi & 0x3FF) << 10 | (lo & 0x3FF)));
+            self.bytes.extend_from_slice(&s[3..]);
+        } else {
+            self.bytes.extend_from_slice(s);
+        }
+    }
+
+    /// Appends `cp` without joining surrogates.
+    fn push_code_point(&mut self, cp: u32) {
+        let mut buf = [0; 4];
+        let n = encode(cp, &mut buf);
+        self.bytes.extend_from_slice(&buf[..n]);
+    }
+}
+
+#[cfg(feature = "std")]
+impl ops::Deref for Wtf8Buf {
+    type Target = Wtf8;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { Wtf8::from_bytes_unchecked(&self.bytes) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Wtf8Buf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Wtf8Buf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsRef<Wtf8> for Wtf8Buf {
+    #[inline]
+    fn as_ref(&self) -> &Wtf8 { self }
+}
+
+#[cfg(feature = "std")]
+impl Borrow<Wtf8> for Wtf8Buf {
+    #[inline]
+    fn borrow(&self) -> &Wtf8 { self }
+}
+
+#[cfg(feature = "std")]
+impl ToOwned for Wtf8 {
+    type Owned = Wtf8Buf;
+
+    #[inline]
+    fn to_owned(&self) -> Wtf8Buf {
+        Wtf8Buf {
+            bytes: self.bytes.to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&str> for Wtf8Buf {
+    #[inline]
+    fn from(x: &str) -> Self { Self::from_string(x.to_string()) }
+}
+
+#[cfg(feature = "std")]
+impl From<String> for Wtf8Buf {
+    #[inline]
+    fn from(x: String) -> Self { Self::from_string(x) }
+}
+
+#[cfg(feature = "std")]
+impl From<&WStr> for Wtf8Buf {
+    #[inline]
+    fn from(x: &WStr) -> Self { Self::from_wstr(x) }
+}
+
+#[cfg(feature = "std")]
+impl WStr {
+    /// Converts [`WStr`] to WTF-8 without loss.
+    ///
+    /// Unlike [`WStr::to_string_lossy`], unpaired surrogates are preserved.
+    #[inline]
+    pub fn to_wtf8(&self) -> Wtf8Buf { Wtf8Buf::from_wstr(self) }
+}
+
+#[cfg(feature = "std")]
+impl WString {
+    /// Converts WTF-8 string to [`WString`] without loss.
+    ///
+    /// If `s` contains a null character, this function returns [`NulError`].
+    #[inline]
+    pub fn from_wtf8(s: &Wtf8) -> Result<Self, NulError<u16>> { s.to_wstring() }
+}
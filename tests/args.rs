@@ -0,0 +1,275 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+#![cfg(feature = "std")]
+use windy::*;
+
+/// Arguments after the program name, and the expected arguments with the
+/// rules since Visual C++ 2008 and prior to Visual C++ 2008.
+///
+/// `CommandLineToArgvW` follows the rules prior to Visual C++ 2008.
+const TABLE: &[(&str, &[&str], &[&str])] = &[
+    ("abc", &["abc"], &["abc"]),
+    ("a b c", &["a", "b", "c"], &["a", "b", "c"]),
+    ("  a\t\tb  ", &["a", "b"], &["a", "b"]),
+    ("a\tb", &["a", "b"], &["a", "b"]),
+    ("\"a\tb\"", &["a\tb"], &["a\tb"]),
+    ("\" \"", &[" "], &[" "]),
+    ("ä 🍣", &["ä", "🍣"], &["ä", "🍣"]),
+    // Examples of the Microsoft C++ documentation.
+    (r#""abc" d e"#, &["abc", "d", "e"], &["abc", "d", "e"]),
+    (
+        r#"a\\b d"e f"g h"#,
+        &[r"a\\b", "de fg", "h"],
+        &[r"a\\b", "de fg", "h"],
+    ),
+    (
+        r#"a\\\"b c d"#,
+        &[r#"a\"b"#, "c", "d"],
+        &[r#"a\"b"#, "c", "d"],
+    ),
+    (
+        r#"a\\\\"b c" d e"#,
+        &[r"a\\b c", "d", "e"],
+        &[r"a\\b c", "d", "e"],
+    ),
+    (r#"a"b"" c d"#, &[r#"ab" c d"#], &[r#"ab""#, "c", "d"]),
+    // Backslashes.
+    (r"a\", &[r"a\"], &[r"a\"]),
+    (r"a\\", &[r"a\\"], &[r"a\\"]),
+    (r"\\\\", &[r"\\\\"], &[r"\\\\"]),
+    (r#"\""#, &[r#"""#], &[r#"""#]),
+    (r#"\\""#, &[r"\"], &[r"\"]),
+    (r#"\\\"a"#, &[r#"\"a"#], &[r#"\"a"#]),
+    (r#"\\\\\""#, &[r#"\\""#], &[r#"\\""#]),
+    (r#"a\"b"#, &[r#"a"b"#], &[r#"a"b"#]),
+    (r#"\a\""#, &[r#"\a""#], &[r#"\a""#]),
+    (r#""a\\" b"#, &[r"a\", "b"], &[r"a\", "b"]),
+    (r#""a\\\" b""#, &[r#"a\" b"#], &[r#"a\" b"#]),
+    (r#""\"""#, &[r#"""#], &[r#"""#]),
+    (
+        r#""ab\"c" "\\" d"#,
+        &[r#"ab"c"#, r"\", "d"],
+        &[r#"ab"c"#, r"\", "d"],
+    ),
+    // Quotes.
+    (r#""a b c""#, &["a b c"], &["a b c"]),
+    (r#"a"b c"d"#, &["ab cd"], &["ab cd"]),
+    (r#""a"b"c""#, &["abc"], &["abc"]),
+    (r#""a b"#, &["a b"], &["a b"]),
+    (r#"a""#, &["a"], &["a"]),
+    (r#""""#, &[""], &[""]),
+    (r#""" """#, &["", ""], &["", ""]),
+    (r#""" a"#, &["", "a"], &["", "a"]),
+    (r#"a "" b"#, &["a", "", "b"], &["a", "", "b"]),
+    // Double quotes in quoted blocks.
+    (r#""a b c"""#, &[r#"a b c""#], &[r#"a b c""#]),
+    (r#"""""#, &[r#"""#], &[r#"""#]),
+    (r#""""""#, &[r#"""#], &[r#"""#]),
+    (r#""""""""#, &[r#""""#], &[r#""""#]),
+    (r#""a""b""#, &[r#"a"b"#], &[r#"a"b"#]),
+    (r#""a"" b""#, &[r#"a" b"#], &[r#"a""#, "b"]),
+    (
+        r#""""CallMeIshmael""" b c"#,
+        &[r#""CallMeIshmael""#, "b", "c"],
+        &[r#""CallMeIshmael""#, "b", "c"],
+    ),
+    (
+        r#""""Call Me Ishmael""""#,
+        &[r#""Call Me Ishmael""#],
+        &[r#""Call"#, "Me", r#"Ishmael""#],
+    ),
+    (
+        r#"""""Call Me Ishmael"" b c"#,
+        &[r#""Call"#, "Me", "Ishmael", "b", "c"],
+        &[r#""Call Me Ishmael""#, "b", "c"],
+    ),
+    (
+        r#""""a""" """b""""#,
+        &[r#""a""#, r#""b""#],
+        &[r#""a""#, r#""b""#],
+    ),
+];
+
+fn parse(s: &str, style: ArgsStyle) -> Vec<String> {
+    WString::from_str(s)
+        .unwrap()
+        .parse_args_with(style)
+        .iter()
+        .map(|x| x.to_string_lossy())
+        .collect()
+}
+
+#[test]
+fn test_parse_args_table() {
+    for &(args, crt, pre2008) in TABLE {
+        let s = format!("EXE {}", args);
+        let mut crt = crt.to_vec();
+        crt.insert(0, "EXE");
+        let mut pre2008 = pre2008.to_vec();
+        pre2008.insert(0, "EXE");
+        assert_eq!(crt, parse(&s, ArgsStyle::Crt), "{}", s);
+        assert_eq!(pre2008, parse(&s, ArgsStyle::CrtPre2008), "{}", s);
+        assert_eq!(pre2008, parse(&s, ArgsStyle::Shell32), "{}", s);
+    }
+}
+
+#[test]
+fn test_parse_args_program() {
+    // The program name, the expected arguments of `CommandLineToArgvW` and
+    // the CRT since Visual C++ 2008.
+    let table: &[(&str, &[&str], &[&str])] = &[
+        ("", &[], &[""]),
+        ("a", &["a"], &["a"]),
+        (" a", &["", "a"], &["", "a"]),
+        ("\ta", &["", "a"], &["", "a"]),
+        (r#""""#, &[""], &[""]),
+        (r#""a"#, &["a"], &["a"]),
+        (r#""a b" c"#, &["a b", "c"], &["a b", "c"]),
+        (r#""a b"c d"#, &["a b", "c", "d"], &["a bc", "d"]),
+        (r#"a"b c" d"#, &[r#"a"b"#, "c d"], &["ab c", "d"]),
+        (r#"C:\x\"y z"#, &[r#"C:\x\"y"#, "z"], &[r"C:\x\y z"]),
+        (r"C:\x\ y", &[r"C:\x\", "y"], &[r"C:\x\", "y"]),
+        (
+            r#""C:\Program Files\a.exe" "b c""#,
+            &[r"C:\Program Files\a.exe", "b c"],
+            &[r"C:\Program Files\a.exe", "b c"],
+        ),
+    ];
+    for &(s, shell32, crt) in table {
+        assert_eq!(shell32, parse(s, ArgsStyle::Shell32), "{}", s);
+        assert_eq!(crt, parse(s, ArgsStyle::Crt), "{}", s);
+        if !s.is_empty() {
+            assert_eq!(shell32, parse(s, ArgsStyle::CrtPre2008), "{}", s);
+        }
+    }
+    assert_eq!([""], parse("", ArgsStyle::CrtPre2008).as_slice());
+    let args = wstr!("a b").parse_args();
+    assert_eq!([wstr!("a"), wstr!("b")], [&*args[0], &*args[1]]);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_parse_args_command_line_to_argv() {
+    #[link(name = "shell32")]
+    unsafe extern "system" {
+        fn CommandLineToArgvW(
+            cmd: *const u16,
+            argc: *mut i32,
+        ) -> *mut *const u16;
+    }
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn LocalFree(mem: *mut core::ffi::c_void) -> *mut core::ffi::c_void;
+    }
+    for &(args, _, _) in TABLE {
+        let s = WString::from_str(&format!("EXE {}", args)).unwrap();
+        let expected = s.parse_args();
+        let actual: Vec<WString> = unsafe {
+            let mut argc = 0;
+            let argv = CommandLineToArgvW(s.as_ptr(), &mut argc);
+            assert!(!argv.is_null());
+            let v = (0..argc as usize)
+                .map(|i| WStr::from_raw(*argv.add(i)).to_wstring())
+                .collect();
+            LocalFree(argv as _);
+            v
+        };
+        assert_eq!(expected, actual, "{}", args);
+    }
+}
+
+#[test]
+fn test_quote_arg() {
+    let table = [
+        ("a", "a"),
+        ("", r#""""#),
+        ("a b", r#""a b""#),
+        ("a\tb", "\"a\tb\""),
+        ("a\nb", "\"a\nb\""),
+        (r"a\b", r"a\b"),
+        (r"a\", r"a\"),
+        (r"a b\", r#""a b\\""#),
+        (r"a b\\", r#""a b\\\\""#),
+        (r#"a"b"#, r#""a\"b""#),
+        (r#"a\"b"#, r#""a\\\"b""#),
+        (r#"\\""#, r#""\\\\\"""#),
+        (r"\\a b", r#""\\a b""#),
+        ("ä🍣", "ä🍣"),
+    ];
+    for (arg, expected) in table {
+        let s = WString::from_str(arg).unwrap().quote_arg();
+        assert_eq!(expected, s.to_string_lossy(), "{}", arg);
+    }
+}
+
+#[test]
+fn test_join_args() {
+    let s = WString::join_str_args(["a b", "", r#"c"d\"#, r"e\"]).unwrap();
+    assert_eq!(r#""a b" "" "c\"d\\" e\"#, s.to_string_lossy());
+    let s = WString::join_str_args([""]).unwrap();
+    assert_eq!(r#""""#, s.to_string_lossy());
+    assert_eq!(
+        [""],
+        parse(&s.to_string_lossy(), ArgsStyle::Shell32).as_slice()
+    );
+    let s = WString::join_args([WString::from_str(r"C:\x").unwrap()]).unwrap();
+    assert_eq!(r"C:\x", s.to_string_lossy());
+    assert_eq!(
+        Err(JoinArgsError::InvalidProgramName),
+        WString::join_str_args([r#"a"b"#, "c"])
+    );
+    assert_eq!(
+        Err(JoinArgsError::InteriorNul { index: 1 }),
+        WString::join_str_args(["a", "b\0"])
+    );
+    let empty: [&WStr; 0] = [];
+    assert_eq!(WString::default(), WString::join_args(empty).unwrap());
+
+    // Length limit of `CreateProcessW`.
+    let long =
+        WString::new(vec![b'a' as u16; UNICODE_STRING_MAX_CHARS - 3]).unwrap();
+    let s = WString::join_args_checked([wstr!("x"), &long]).unwrap();
+    assert_eq!(UNICODE_STRING_MAX_CHARS - 1, s.to_bytes().len());
+    assert_eq!(
+        Err(JoinArgsError::TooLong {
+            len: UNICODE_STRING_MAX_CHARS
+        }),
+        WString::join_args_checked([wstr!("xy"), &long])
+    );
+}
+
+/// A xorshift generator for reproducible random arguments.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn arg(&mut self, chars: &[char]) -> String {
+        let len = self.next(8);
+        (0..len).map(|_| chars[self.next(chars.len())]).collect()
+    }
+}
+
+#[test]
+fn test_join_args_round_trip() {
+    const CHARS: &[char] = &['a', ' ', '\t', '"', '\\', '\\', 'ä', '🍣'];
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..10000 {
+        let mut args = vec![rng.arg(&CHARS[..CHARS.len() - 1])];
+        args[0].retain(|c| c != '"');
+        for _ in 0..rng.next(5) {
+            args.push(rng.arg(CHARS));
+        }
+        let s = WString::join_str_args(&args).unwrap();
+        let s = s.to_string_lossy();
+        for style in [ArgsStyle::Shell32, ArgsStyle::CrtPre2008, ArgsStyle::Crt]
+        {
+            assert_eq!(args, parse(&s, style), "{:?} {}", style, s);
+        }
+    }
+}
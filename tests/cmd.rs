@@ -0,0 +1,355 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+#![cfg(feature = "std")]
+use windy::*;
+
+fn parse(s: &str, style: ArgsStyle) -> Vec<String> {
+    WString::from_str(s)
+        .unwrap()
+        .parse_args_with(style)
+        .iter()
+        .map(|x| x.to_string_lossy())
+        .collect()
+}
+
+/// Simulates how `cmd.exe /c` processes a command line, and returns the
+/// command token that `cmd.exe` runs and the rest of the command line that
+/// the program receives.
+///
+/// If `cmd.exe` interprets a special character, this function returns it.
+fn run_cmd(s: &str, delayed: bool) -> Result<(String, String), char> {
+    let env = |name: &str| -> Option<&'static str> {
+        match name.to_ascii_uppercase().as_str() {
+            "PATH" => Some(r"C:\Windows"),
+            "X" => Some("&calc&"),
+            "CD" => Some(r"C:\"),
+            _ => None,
+        }
+    };
+    // Percent expansion in the command line context, including `%VAR:~n,m%`
+    // and `%VAR:a=b%`. An undefined variable is left intact.
+    let c: Vec<char> = s.chars().collect();
+    let mut s = String::new();
+    let mut i = 0;
+    while i < c.len() {
+        if c[i] == '%'
+            && let Some(j) = c[i + 1..].iter().position(|&x| x == '%')
+        {
+            let name: String = c[i + 1..i + 1 + j].iter().collect();
+            if let Some(v) = expand(&name, &env) {
+                s.push_str(&v);
+                i += j + 2;
+                continue;
+            }
+        }
+        s.push(c[i]);
+        i += 1;
+    }
+    // Carets, quotes and special characters. An escaped quote does not
+    // start a quoted block, and the command token ends at the first
+    // unescaped space or tab outside quoted blocks.
+    let c: Vec<char> = s.chars().collect();
+    let mut s = String::new();
+    let mut command_len = None;
+    let mut quoted = false;
+    let mut i = 0;
+    while i < c.len() {
+        let x = c[i];
+        i += 1;
+        if quoted {
+            quoted = x != '"';
+        } else {
+            match x {
+                // A caret at the end continues the line.
+                '^' => match c.get(i) {
+                    Some(&y) => {
+                        s.push(y);
+                        i += 1;
+                        continue;
+                    }
+                    None => return Err('^'),
+                },
+                '"' => quoted = true,
+                '&' | '|' | '<' | '>' | '(' | ')' => return Err(x),
+                ' ' | '\t' if command_len.is_none() => {
+                    command_len = Some(s.len())
+                }
+                _ => {}
+            }
+        }
+        s.push(x);
+    }
+    let args = s.split_off(command_len.unwrap_or(s.len()));
+    if !delayed {
+        return Ok((s, args));
+    }
+    // Delayed expansion of the command token and the arguments.
+    Ok((delayed_expand(&s, &env), delayed_expand(&args, &env)))
+}
+
+/// Expands `%VAR%`, `%VAR:~n,m%` and `%VAR:a=b%`.
+fn expand(
+    name: &str,
+    env: &impl Fn(&str) -> Option<&'static str>,
+) -> Option<String> {
+    let Some((name, modifier)) = name.split_once(':') else {
+        return env(name).map(String::from);
+    };
+    let v: Vec<char> = env(name)?.chars().collect();
+    if let Some(range) = modifier.strip_prefix('~') {
+        // Numbers are parsed like `atoi`, and negative numbers count from
+        // the end.
+        let num = |s: &str| {
+            let s = s.trim_start();
+            let (neg, s) = match s.strip_prefix('-') {
+                Some(s) => (true, s),
+                None => (false, s),
+            };
+            let n = s
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .fold(0, |n, x| n * 10 + x.to_digit(10).unwrap() as isize);
+            if neg { -n } else { n }
+        };
+        let len = v.len() as isize;
+        let (start, count) = match range.split_once(',') {
+            Some((a, b)) => (num(a), Some(num(b))),
+            None => (num(range), None),
+        };
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start.min(len)
+        };
+        let end = match count {
+            None => len,
+            Some(n) if n < 0 => (len + n).max(start),
+            Some(n) => (start + n).min(len),
+        };
+        return Some(v[start as usize..end as usize].iter().collect());
+    }
+    let (from, to) = modifier.split_once('=')?;
+    let v: String = v.into_iter().collect();
+    if from.is_empty() {
+        return Some(v);
+    }
+    // Case-insensitive replacement of ASCII strings.
+    let (lower, from) = (v.to_ascii_lowercase(), from.to_ascii_lowercase());
+    let mut s = String::new();
+    let mut i = 0;
+    while let Some(j) = lower[i..].find(&from) {
+        s.push_str(&v[i..i + j]);
+        s.push_str(to);
+        i += j + from.len();
+    }
+    s.push_str(&v[i..]);
+    Some(s)
+}
+
+/// Expands `!VAR!` in a token if it contains `!`. Carets are processed
+/// again, and an undefined variable and a lone `!` are removed.
+fn delayed_expand(
+    s: &str,
+    env: &impl Fn(&str) -> Option<&'static str>,
+) -> String {
+    if !s.contains('!') {
+        return s.to_string();
+    }
+    let c: Vec<char> = s.chars().collect();
+    let mut s = String::new();
+    let mut i = 0;
+    while i < c.len() {
+        match c[i] {
+            '^' => {
+                s.extend(c.get(i + 1));
+                i += 2;
+            }
+            '!' => match c[i + 1..].iter().position(|&x| x == '!') {
+                Some(j) => {
+                    let name: String = c[i + 1..i + 1 + j].iter().collect();
+                    s.push_str(env(&name).unwrap_or(""));
+                    i += j + 2;
+                }
+                None => i += 1,
+            },
+            x => {
+                s.push(x);
+                i += 1;
+            }
+        }
+    }
+    s
+}
+
+const HOSTILE: &[&str] = &[
+    "",
+    "abc",
+    "&calc",
+    "a & calc",
+    "a&&calc||b",
+    "a|calc",
+    "a > out.txt",
+    "<in.txt",
+    "(calc)",
+    ")",
+    "^",
+    "a^",
+    "^^",
+    "a^&calc",
+    "%PATH%",
+    "%path%",
+    "%X%",
+    "%X%X%",
+    "%X^%",
+    "%%X%%",
+    "100%",
+    "%",
+    "%=C:%",
+    "%X:~0,1%",
+    "%X:~1%",
+    "%X:~-2,-1%",
+    "%X:c=d%",
+    "%X:a=b%X%",
+    "%PATH:a=b%",
+    "%CD:~,%",
+    "%%",
+    "%%%",
+    "%^",
+    "%^%",
+    "a%^^b%!",
+    "%!X!%",
+    "% a%",
+    "!X!",
+    "!PATH!",
+    "!",
+    "!!",
+    "a!b",
+    "^!X^!",
+    "!^X!",
+    "\"",
+    "\"&calc\"",
+    "\"&calc&\"",
+    "\"\"&calc",
+    r#"\"&calc&\""#,
+    r#"a" & calc & "b"#,
+    r#""a\" & calc & "b""#,
+    "\t&\tcalc",
+    "ä&🍣|%X%!X!",
+];
+
+/// Returns the command line that the program receives.
+fn run_line(s: &str, delayed: bool) -> Result<String, char> {
+    run_cmd(s, delayed).map(|(command, args)| command + &args)
+}
+
+#[test]
+fn test_cmd_escape() {
+    let esc = |s: &str, delayed: bool| {
+        WString::from_str(s)
+            .unwrap()
+            .cmd_escape(delayed)
+            .map(|x| x.to_string_lossy())
+            .map_err(|e| e.position())
+    };
+    let ok = |s: &str, delayed: bool| esc(s, delayed).unwrap();
+    assert_eq!("^&calc", ok("&calc", false));
+    assert_eq!(r#"a ^"b^(c^)^"^|^<^>"#, ok(r#"a "b(c)"|<>"#, false));
+    assert_eq!("%^PATH^%", ok("%PATH%", false));
+    assert_eq!("%^PATH:a=b^%", ok("%PATH:a=b%", false));
+    assert_eq!("%^%^X^%", ok("%%X%", false));
+    assert_eq!("%^^a", ok("%^a", false));
+    assert_eq!("a^^b!", ok("a^b!", false));
+    assert_eq!("a^^b", ok("a^b", true));
+    assert_eq!("a^^^^b^^!", ok("a^b!", true));
+    // `!` in the arguments does not affect the command token.
+    assert_eq!("a^^b c^^^^d^^!", ok("a^b c^d!", true));
+    // The quotes of the command token are not escaped, and `cmd.exe`
+    // interprets only `%` and `!` in its quoted blocks.
+    assert_eq!(r#""a(b)"^|^<^>"#, ok(r#""a(b)"|<>"#, false));
+    assert_eq!(r#""a^ b!" c^^d"#, ok(r#""a^ b!" c^d"#, false));
+    assert_eq!(r#""a^^ b^!" c^^d"#, ok(r#""a^ b!" c^d"#, true));
+    assert_eq!(r#""a b"^^^^c^^^^^^!"#, ok(r#""a b"^c^!"#, true));
+    // `%` that cannot be followed by `^` in the command token.
+    assert_eq!(Err(1), esc(r#""%PATH%" a"#, false));
+    assert_eq!(Err(1), esc(r#"a%"b c""#, false));
+    assert_eq!(Err(1), esc("a% b", false));
+    assert_eq!(Ok("a^%".to_string()), esc("a%", false));
+    assert_eq!(Ok("a %^ b".to_string()), esc("a % b", false));
+
+    // The simulator interprets unescaped inputs.
+    let ok = |s: &str| Ok(s.to_string());
+    assert_eq!(Err('&'), run_line("a&calc", false));
+    assert_eq!(ok(r"C:\Windows"), run_line("%PATH%", false));
+    assert_eq!(ok(r"C:\Windows"), run_line("!PATH!", true));
+    assert_eq!(ok(r"C:\xindoxs"), run_line("%PATH:W=x%", false));
+    assert_eq!(Err('&'), run_line("%X:a=b%", false));
+    // A caret before `%` does not prevent `%VAR:~n,m%`.
+    assert_eq!(Err('&'), run_line("^%X:~1^%", false));
+    assert_eq!(ok("l"), run_line("%X:~-3,1%", false));
+    assert_eq!(ok("a^b ^c!"), run_line("a^^b ^^^^c^^!", true));
+    assert_eq!(ok(r#""a^b""#), run_line(r#""a^b""#, false));
+    // An escaped quote does not start a quoted block, so the command token
+    // ends at the space.
+    let split = |a: &str, b: &str| Ok((a.to_string(), b.to_string()));
+    assert_eq!(split(r#""a"#, r#" b" c"#), run_cmd(r#"^"a b^" c"#, false));
+    assert_eq!(split(r#""a b""#, " c"), run_cmd(r#""a b" c"#, false));
+    assert_eq!(split("a b", ""), run_cmd("a^ b", false));
+    // Delayed expansion is decided by the command token that `cmd.exe`
+    // finds, so `^^` reaches the program if the quotes are escaped.
+    assert_eq!(
+        split(r#""a^^"#, r#" b!" cd"#),
+        run_cmd(r#"^"a^^^^ b^^!^" c^^d"#, true)
+    );
+
+    for &s in HOSTILE {
+        for delayed in [false, true] {
+            match esc(s, delayed) {
+                Ok(e) => {
+                    assert_eq!(ok(s), run_line(&e, delayed), "{} {}", s, e)
+                }
+                // Only `%` of the command token cannot be escaped.
+                Err(i) => {
+                    assert_eq!(Some(b'%' as u16), s.encode_utf16().nth(i))
+                }
+            }
+            let s = format!("p {}", s);
+            let e = esc(&s, delayed).unwrap();
+            assert_eq!(ok(&s), run_line(&e, delayed), "{} {}", s, e);
+        }
+    }
+
+    for s in ["a\nb", "a\r\nb", "\r"] {
+        let e = WString::from_str(s).unwrap().cmd_escape(false).unwrap_err();
+        assert_eq!(s.find(['\r', '\n']), Some(e.position()));
+    }
+}
+
+#[test]
+fn test_cmd_escape_args() {
+    // `cmd.exe` runs the program, and the arguments pass through `cmd.exe`
+    // and the CRT without change.
+    let programs = [r"C:\a^b\prog", r"C:\Program Files\a^b!(&)\prog"];
+    for program in programs {
+        for &s in HOSTILE {
+            let args = [program, s, "x y", s];
+            let line = WString::join_str_args(args).unwrap();
+            for delayed in [false, true] {
+                let e = line.cmd_escape(delayed).unwrap().to_string_lossy();
+                let (command, rest) = run_cmd(&e, delayed).unwrap();
+                assert_eq!([program], parse(&command, ArgsStyle::Crt)[..]);
+                let received = command + &rest;
+                assert_eq!(line.to_string_lossy(), received);
+                for style in [ArgsStyle::Shell32, ArgsStyle::Crt] {
+                    assert_eq!(args.as_slice(), parse(&received, style));
+                }
+            }
+        }
+    }
+    // The program name with a space is not split by `cmd.exe`.
+    let line =
+        WString::join_str_args([r"C:\Program Files\x.exe", "a"]).unwrap();
+    let e = line.cmd_escape(false).unwrap().to_string_lossy();
+    assert_eq!(r#""C:\Program Files\x.exe" a"#, e);
+    let (command, _) = run_cmd(&e, false).unwrap();
+    assert_eq!(r#""C:\Program Files\x.exe""#, command);
+}
@@ -0,0 +1,361 @@
+// Copyright takubokudori.
+// This source code is licensed under the MIT or Apache-2.0 license.
+#![cfg(feature = "std")]
+use windy::*;
+
+fn p(s: &str) -> WPathBuf { WPathBuf::from_str(s).unwrap() }
+
+fn lossy(x: Option<&WPath>) -> Option<String> { x.map(WPath::to_string_lossy) }
+
+/// Returns the components as strings.
+fn components(s: &str) -> Vec<String> {
+    p(s).components()
+        .map(|c| match c {
+            WComponent::Prefix(x) => format!("{:?}", x),
+            WComponent::RootDir => r"\".to_string(),
+            WComponent::CurDir => ".".to_string(),
+            WComponent::ParentDir => "..".to_string(),
+            WComponent::Normal(x) => x.to_string_lossy(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_path_prefix() {
+    let w = |s: &str| p(s);
+    let (a, b) = (w("a"), w("b"));
+    let table = [
+        ("", None),
+        ("a", None),
+        (r"\a", None),
+        ("C:", Some(WPrefix::Disk(b'C'))),
+        ("c:a", Some(WPrefix::Disk(b'c'))),
+        (r"C:\a", Some(WPrefix::Disk(b'C'))),
+        ("C:/a", Some(WPrefix::Disk(b'C'))),
+        (r"\\a\b\c", Some(WPrefix::UNC(&a, &b))),
+        ("//a/b/c", Some(WPrefix::UNC(&a, &b))),
+        (r"\\a\b", Some(WPrefix::UNC(&a, &b))),
+        (r"\\.\a\b", Some(WPrefix::DeviceNS(&a))),
+        ("//./a", Some(WPrefix::DeviceNS(&a))),
+        (r"\\?\a\b", Some(WPrefix::Verbatim(&a))),
+        (r"\\?\C:\a", Some(WPrefix::VerbatimDisk(b'C'))),
+        (r"\\?\C:", Some(WPrefix::VerbatimDisk(b'C'))),
+        (r"\\?\UNC\a\b\c", Some(WPrefix::VerbatimUNC(&a, &b))),
+        (r"\??\a\b", Some(WPrefix::Nt(&a))),
+    ];
+    for (s, expected) in table {
+        assert_eq!(expected, p(s).prefix(), "{}", s);
+    }
+    // `/` is not a separator of verbatim paths.
+    let c = w("C:/a");
+    assert_eq!(Some(WPrefix::Verbatim(&c)), p(r"\\?\C:/a").prefix());
+    // `?` is a device namespace unless the prefix is exactly `\\?\`.
+    let d = w("C:");
+    assert_eq!(Some(WPrefix::DeviceNS(&d)), p("//?/C:/a").prefix());
+    assert_eq!(Some(WPrefix::DeviceNS(&d)), p(r"\\?/C:\a").prefix());
+    assert!(WPrefix::Nt(&a).is_verbatim());
+    assert!(!WPrefix::UNC(&a, &b).is_verbatim());
+}
+
+#[test]
+fn test_path_components() {
+    let table: &[(&str, &[&str])] = &[
+        ("", &[]),
+        ("a", &["a"]),
+        (r"a\b", &["a", "b"]),
+        ("a/b//c/", &["a", "b", "c"]),
+        (r".\a\.\b\..\.", &[".", "a", "b", ".."]),
+        ("..", &[".."]),
+        (r"\a", &[r"\", "a"]),
+        ("C:", &["Disk(67)"]),
+        ("C:a", &["Disk(67)", "a"]),
+        (r"C:\a", &["Disk(67)", r"\", "a"]),
+        (r"C:.\a", &["Disk(67)", "a"]),
+        (r"\\s\sh\a", &[r#"UNC("s", "sh")"#, r"\", "a"]),
+        (r"\\s\sh", &[r#"UNC("s", "sh")"#, r"\"]),
+        (r"\\.\COM1", &[r#"DeviceNS("COM1")"#, r"\"]),
+        (
+            r"\\?\C:\a\.\b/c",
+            &["VerbatimDisk(67)", r"\", "a", ".", "b/c"],
+        ),
+        (r"\??\C:\a", &[r#"Nt("C:")"#, r"\", "a"]),
+    ];
+    for &(s, expected) in table {
+        assert_eq!(expected, components(s).as_slice(), "{}", s);
+    }
+}
+
+#[test]
+fn test_path_parent() {
+    let table = [
+        ("", None, None),
+        ("a", Some(""), Some("a")),
+        (r"a\b", Some("a"), Some("b")),
+        (r"a\b\\", Some("a"), Some("b")),
+        (r"a\b\.", Some("a"), Some("b")),
+        (r"a\..", Some("a"), None),
+        (".", Some(""), None),
+        (r"\", None, None),
+        (r"\a", Some(r"\"), Some("a")),
+        ("C:", None, None),
+        ("C:a", Some("C:"), Some("a")),
+        (r"C:\", None, None),
+        (r"C:\a", Some(r"C:\"), Some("a")),
+        (r"C:\a\b", Some(r"C:\a"), Some("b")),
+        ("C:/a//b", Some("C:/a"), Some("b")),
+        (r"\\s\sh", None, None),
+        (r"\\s\sh\", None, None),
+        (r"\\s\sh\a", Some(r"\\s\sh\"), Some("a")),
+        (r"\\?\C:\", None, None),
+        (r"\\?\C:\a", Some(r"\\?\C:\"), Some("a")),
+        (r"\\?\C:\a/b", Some(r"\\?\C:\"), Some("a/b")),
+        (r"\??\C:\a", Some(r"\??\C:\"), Some("a")),
+    ];
+    for (s, parent, file_name) in table {
+        let x = p(s);
+        assert_eq!(parent.map(String::from), lossy(x.parent()), "{}", s);
+        assert_eq!(file_name.map(String::from), lossy(x.file_name()), "{}", s);
+    }
+    let mut x = p(r"C:\a\b");
+    assert!(x.pop());
+    assert_eq!(r"C:\a", x.to_string_lossy());
+    assert!(x.pop());
+    assert!(!x.pop());
+    assert_eq!(r"C:\", x.to_string_lossy());
+}
+
+#[test]
+fn test_path_join() {
+    let table = [
+        ("", "a", "a"),
+        ("a", "b", r"a\b"),
+        (r"a\", "b", r"a\b"),
+        ("a/", "b", "a/b"),
+        ("C:", "a", "C:a"),
+        (r"C:\", "a", r"C:\a"),
+        (r"C:\a", r"\b", r"C:\b"),
+        (r"C:\a", r"D:\b", r"D:\b"),
+        (r"C:\a", "D:b", "D:b"),
+        (r"\\s\sh\a", r"\b", r"\\s\sh\b"),
+        (r"\\s\sh", "b", r"\\s\sh\b"),
+        (r"a", r"\\s\sh\b", r"\\s\sh\b"),
+        (r"\\?\C:\a", "b", r"\\?\C:\a\b"),
+        (r"\\?\C:\a", r"\b", r"\\?\C:\b"),
+        // `.`, `..` and `/` are resolved for verbatim paths.
+        (r"\\?\C:\a", r"..\b/c", r"\\?\C:\b\c"),
+        (r"\\?\C:\a\", r".\b\.", r"\\?\C:\a\b"),
+        (r"\\?\C:\", "../..", r"\\?\C:\"),
+        (r"\\?\C:\a", "/b", r"\\?\C:\b"),
+        (r"\\?\UNC\s\sh\a", r"..\b", r"\\?\UNC\s\sh\b"),
+        (r"\??\C:\a", "b/c", r"\??\C:\a\b\c"),
+        (r"\a", "b", r"\a\b"),
+    ];
+    for (base, path, expected) in table {
+        let s = p(base).join(p(path));
+        assert_eq!(expected, s.to_string_lossy(), "{} {}", base, path);
+    }
+}
+
+#[test]
+fn test_path_normalize() {
+    let table = [
+        ("", "."),
+        (".", "."),
+        ("a", "a"),
+        (r".\a\.\b\", r"a\b"),
+        (r"a\..", "."),
+        (r"a\..\..\b", r"..\b"),
+        (r"..\..", r"..\.."),
+        ("a//b/./c/../d", r"a\b\d"),
+        (r"\a\..\..\b", r"\b"),
+        (r"C:/a/.\b\..\..\..\c\", r"C:\c"),
+        ("C:a/../..", "C:.."),
+        ("C:", "C:"),
+        (r"C:\..", r"C:\"),
+        ("//server/share/a/../../b", r"\\server\share\b"),
+        (r"\\.\COM1\..", r"\\.\COM1\"),
+        (r"\\.\COM1", r"\\.\COM1"),
+        ("//?/C:/a", r"\\.\C:\a"),
+        (r"\\server", r"\\server"),
+        (r"\\server\", r"\\server\"),
+        (r"\\", r"\\"),
+        (r"\\?\C:\a\..\.\b/c", r"\\?\C:\a\..\.\b/c"),
+        (r"\??\C:\a\..", r"\??\C:\a\.."),
+    ];
+    for (s, expected) in table {
+        assert_eq!(expected, p(s).normalize().to_string_lossy(), "{}", s);
+    }
+}
+
+#[test]
+fn test_path_misc() {
+    assert!(p(r"C:\a").is_absolute());
+    assert!(p(r"\\s\sh").is_absolute());
+    assert!(p(r"\\?\a").is_absolute());
+    assert!(p(r"\\.\COM1").is_absolute());
+    assert!(p(r"\??\C:").is_absolute());
+    assert!(p("C:a").is_relative());
+    assert!(p(r"\a").is_relative());
+    assert!(p(r"\a").has_root());
+    assert!(!p("C:a").has_root());
+
+    // Paths are compared by their components.
+    assert_eq!(p(r"C:\a\b"), p("C:/a//b/"));
+    assert_eq!(p(r"a\.\b"), p("a/b"));
+    // Drive letters are case-insensitive, but other components are not.
+    assert_eq!(p(r"C:\a"), p(r"c:\a"));
+    assert_eq!(p(r"\\?\C:\a"), p(r"\\?\c:\a"));
+    assert_ne!(p(r"C:\a"), p(r"C:\A"));
+    assert_ne!(p(r"\\?\C:\a/b"), p(r"\\?\C:\a\b"));
+    let set: std::collections::HashSet<WPathBuf> =
+        [p(r"C:\a"), p("C:/a/"), p(r"c:\a")].into_iter().collect();
+    assert_eq!(1, set.len());
+
+    let x = p(r"C:\a");
+    assert_eq!(wstr!(r"C:\a"), x.as_wstr());
+    assert_eq!(WPath::new(wstr!(r"C:\a")), &*x);
+    assert_eq!(r#""C:\\a""#, format!("{:?}", x));
+    assert_eq!(x, WPathBuf::from(wstr!(r"C:\a")));
+    assert!(<&WPath>::default().is_empty());
+    assert_eq!(wstr!(r"C:\a").to_wstring(), WString::from(x));
+}
+
+/// Win32 paths and NT paths that `RtlDosPathNameToNtPathName_U` returns.
+const NT_PATHS: &[(&str, &str)] = &[
+    (r"C:\a\b", r"\??\C:\a\b"),
+    ("C:/a//b/", r"\??\C:\a\b\"),
+    (r"c:\a\.\b\..\c", r"\??\c:\a\c"),
+    (r"C:\..\..\a", r"\??\C:\a"),
+    (r"C:\a\b\..", r"\??\C:\a"),
+    (r"C:\a\.", r"\??\C:\a"),
+    (r"C:\.", r"\??\C:\"),
+    (r"C:\a. \b.\c. . ", r"\??\C:\a. \b\c"),
+    (r"C:\a..\b", r"\??\C:\a.\b"),
+    (r"C:\.a\..b", r"\??\C:\.a\..b"),
+    (r"\\server\share\a\..\..\b", r"\??\UNC\server\share\b"),
+    ("//server/share", r"\??\UNC\server\share"),
+    (r"\\server\share\CON", r"\??\UNC\server\share\CON"),
+    (r"\\.\COM1", r"\??\COM1"),
+    (r"\\.\C:\a\..\b", r"\??\C:\b"),
+    (r"\\.", r"\??\"),
+    ("//?/C:/a/../b", r"\??\C:\b"),
+    (r"\\?\C:\a\..\b. ", r"\??\C:\a\..\b. "),
+    (r"\??\C:\a/..", r"\??\C:\a/.."),
+    (r"C:\CONX", r"\??\C:\CONX"),
+    (r"C:\COM0", r"\??\C:\COM0"),
+];
+
+/// Paths that contain DOS device names, which depend on the version of
+/// Windows.
+const DEVICE_PATHS: &[(&str, &str)] = &[
+    ("CON", r"\??\CON"),
+    (r"C:\a\con.txt", r"\??\con"),
+    ("COM1:", r"\??\COM1"),
+    ("nul .txt", r"\??\nul"),
+    (r"\a\AUX", r"\??\AUX"),
+    ("C:lpt9.x.y", r"\??\lpt9"),
+];
+
+#[test]
+fn test_to_nt_path() {
+    for &(s, expected) in NT_PATHS.iter().chain(DEVICE_PATHS) {
+        let x = WString::from_str(s).unwrap();
+        assert_eq!(
+            expected,
+            x.to_nt_path().unwrap().to_string_lossy(),
+            "{}",
+            s
+        );
+        let verbatim = format!(r"\\?\{}", &expected[4..]);
+        assert_eq!(verbatim, x.to_verbatim_path().unwrap().to_string_lossy());
+    }
+    for s in ["", "a", r"\a", "C:a", r"..\C:\a", "/a/b"] {
+        let x = WString::from_str(s).unwrap();
+        assert_eq!(Err(NtPathError::RelativePath), x.to_nt_path(), "{}", s);
+        assert_eq!(Err(NtPathError::RelativePath), x.to_verbatim_path());
+    }
+}
+
+#[test]
+fn test_nt_to_win32_path() {
+    let table = [
+        (r"\??\C:\a", r"C:\a"),
+        (r"\??\C:\", r"C:\"),
+        (r"\??\C:\a.", r"\\?\C:\a."),
+        (r"\??\C:\a\..\b", r"\\?\C:\a\..\b"),
+        (r"\??\C:\CON", r"\\?\C:\CON"),
+        (r"\??\C:", r"\\.\C:"),
+        (r"\??\UNC\s\sh\a", r"\\s\sh\a"),
+        (r"\??\COM1", r"\\.\COM1"),
+        (r"\??\CON", r"\\.\CON"),
+        (r"\??\Volume{0}\a", r"\\.\Volume{0}\a"),
+        (r"\DosDevices\C:\a", r"C:\a"),
+        (r"\GLOBAL??\C:\a", r"C:\a"),
+        (r"\Device\Mup\s\sh", r"\\s\sh"),
+        (r"\Device\Null", r"\\.\NUL"),
+        (r"\Device\NullX", r"\\?\GLOBALROOT\Device\NullX"),
+        (
+            r"\Device\HarddiskVolume1\a",
+            r"\\?\GLOBALROOT\Device\HarddiskVolume1\a",
+        ),
+    ];
+    for (s, expected) in table {
+        let x = WString::from_str(s).unwrap();
+        let actual = x.nt_to_win32_path().unwrap();
+        assert_eq!(expected, actual.to_string_lossy(), "{}", s);
+    }
+    for s in ["", "a", r"C:\a"] {
+        let x = WString::from_str(s).unwrap();
+        assert_eq!(Err(NtPathError::NotNtPath), x.nt_to_win32_path(), "{}", s);
+    }
+    // The Win32 path is converted back into the same NT path.
+    for &(_, nt) in NT_PATHS.iter().chain(DEVICE_PATHS) {
+        let x = WString::from_str(nt).unwrap();
+        let win32 = x.nt_to_win32_path().unwrap();
+        assert_eq!(x, win32.to_nt_path().unwrap(), "{}", nt);
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn test_to_nt_path_rtl() {
+    #[repr(C)]
+    struct UnicodeString {
+        length: u16,
+        maximum_length: u16,
+        buffer: *mut u16,
+    }
+    #[link(name = "ntdll")]
+    unsafe extern "system" {
+        fn RtlDosPathNameToNtPathName_U(
+            dos_name: *const u16,
+            nt_name: *mut UnicodeString,
+            file_part: *mut *mut u16,
+            reserved: *mut core::ffi::c_void,
+        ) -> u8;
+        fn RtlFreeUnicodeString(s: *mut UnicodeString);
+    }
+    for &(s, expected) in NT_PATHS {
+        let x = WString::from_str(s).unwrap();
+        let actual = unsafe {
+            let mut us = UnicodeString {
+                length: 0,
+                maximum_length: 0,
+                buffer: core::ptr::null_mut(),
+            };
+            let ret = RtlDosPathNameToNtPathName_U(
+                x.as_ptr(),
+                &mut us,
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+            );
+            assert_ne!(0, ret, "{}", s);
+            let v =
+                core::slice::from_raw_parts(us.buffer, us.length as usize / 2);
+            let v = String::from_utf16_lossy(v);
+            RtlFreeUnicodeString(&mut us);
+            v
+        };
+        assert_eq!(expected, actual, "{}", s);
+    }
+}
@@ -6,6 +6,7 @@ pub mod tests {
     use std::convert::TryFrom;
     use windy::*;
 
+    #[cfg(windows)]
     const ERROR_NO_UNICODE_TRANSLATION: u32 = 1113;
     macro_rules! wn {
         ($x:expr) => {
@@ -17,6 +18,7 @@ pub mod tests {
             unsafe { WString::new_c_unchecked($x) }
         };
     }
+    #[cfg(windows)]
     macro_rules! an {
         ($x:expr) => {
             unsafe { AString::new_unchecked($x) }
@@ -77,6 +79,7 @@ pub mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_astring() {
         let x = an!(vec![]); // empty vec
         assert_eq!(&[0x00], x.as_bytes_with_nul());
@@ -119,6 +122,821 @@ pub mod tests {
     }
 
     #[test]
+    fn test_new_rejects_nul() {
+        let x = WString::new(vec![0x74, 0x65, 0x73, 0x74]).unwrap(); // test
+        assert_eq!(&[0x74, 0x65, 0x73, 0x74, 0x00], x.as_bytes_with_nul());
+        let x = WString::new(vec![]).unwrap();
+        assert_eq!(&[0x00], x.as_bytes_with_nul());
+        let e = WString::new(vec![0x74, 0x65, 0x00, 0x73, 0x74]).unwrap_err(); // te\0st
+        assert_eq!(2, e.nul_position());
+        assert_eq!(vec![0x74, 0x65, 0x00, 0x73, 0x74], e.into_vec());
+        let e = WString::new(vec![0x74, 0x00]).unwrap_err(); // t\0
+        assert_eq!(1, e.nul_position());
+
+        let x = AString::new(b"test".to_vec()).unwrap();
+        assert_eq!(b"test\0", x.as_bytes_with_nul());
+        let e = AString::new(b"te\0st".to_vec()).unwrap_err();
+        assert_eq!(2, e.nul_position());
+        assert_eq!(b"te\0st".to_vec(), e.into_vec());
+    }
+
+    #[test]
+    fn test_from_vec_with_nul() {
+        let x = WString::from_vec_with_nul(vec![0x74, 0x65, 0x00]).unwrap();
+        assert_eq!(&[0x74, 0x65, 0x00], x.as_bytes_with_nul());
+        let e = WString::from_vec_with_nul(vec![0x74, 0x00, 0x65, 0x00])
+            .unwrap_err();
+        assert_eq!(
+            FromBytesWithNulError::InteriorNul { position: 1 },
+            e.error()
+        );
+        assert_eq!(&[0x74, 0x00, 0x65, 0x00], e.as_bytes());
+        let e = WString::from_vec_with_nul(vec![]).unwrap_err();
+        assert_eq!(
+            FromBytesWithNulError::NotNulTerminated { position: 0 },
+            e.error()
+        );
+
+        let x = AString::from_vec_with_nul(b"te\0".to_vec()).unwrap();
+        assert_eq!(b"te\0", x.as_bytes_with_nul());
+        let e = AString::from_vec_with_nul(b"te".to_vec()).unwrap_err();
+        assert_eq!(2, e.error().nul_position());
+        assert_eq!(b"te".to_vec(), e.into_bytes());
+    }
+
+    #[test]
+    fn test_from_u16le_bytes() {
+        // unaligned
+        let b = [0xFF, 0x74, 0x00, 0x65, 0x00, 0x73, 0x00, 0x74, 0x00];
+        let x = WString::from_u16le_bytes(&b[1..]).unwrap();
+        assert_eq!(&[0x74, 0x65, 0x73, 0x74, 0x00], x.as_bytes_with_nul());
+        assert_eq!(
+            Err(FromU16BytesError::OddLength { len: 9 }),
+            WString::from_u16le_bytes(&b)
+        );
+        let x =
+            WString::from_u16le_vec(vec![0x3C, 0xD8, 0x63, 0xDF, 0x00, 0x00])
+                .unwrap(); // 🍣\0
+        assert_eq!(&[0xD83C, 0xDF63, 0x00], x.as_bytes_with_nul());
+        let x = WString::from_u16le_vec(vec![]).unwrap();
+        assert_eq!(&[0x00], x.as_bytes_with_nul());
+        let x = WString::from_u16le_bytes(&[0x3C, 0xD8, 0x74, 0x00]).unwrap();
+        assert_eq!(&[0xD83C, 0x74, 0x00], x.as_bytes_with_nul());
+        assert_eq!(
+            Err(FromU16BytesError::UnpairedSurrogate { position: 0 }),
+            WString::from_u16le_bytes_strict(&[0x3C, 0xD8, 0x74, 0x00])
+        );
+        assert_eq!(
+            Err(FromU16BytesError::UnpairedSurrogate { position: 1 }),
+            WString::from_u16le_bytes_strict(&[0x74, 0x00, 0x63, 0xDF])
+        );
+        let x = WString::from_u16le_bytes_strict(&[0x3C, 0xD8, 0x63, 0xDF])
+            .unwrap();
+        assert_eq!(&[0xD83C, 0xDF63, 0x00], x.as_bytes_with_nul());
+    }
+
+    #[test]
+    fn test_utf16_byte_order() {
+        let b = [0xD8, 0x3C, 0xDF, 0x63, 0x00, 0x74]; // 🍣t
+        let x = WString::from_utf16be_bytes(&b).unwrap();
+        assert_eq!(&[0xD83C, 0xDF63, 0x74, 0x00], x.as_bytes_with_nul());
+        assert_eq!(b.to_vec(), x.to_utf16be_bytes());
+        assert_eq!(
+            vec![0x3C, 0xD8, 0x63, 0xDF, 0x74, 0x00],
+            x.to_utf16le_bytes()
+        );
+        assert_eq!(
+            &[0xD8, 0x3C, 0xDF, 0x63, 0x00, 0x74, 0x00, 0x00],
+            &*x.to_u8_bytes_with_nul_in(ByteOrder::BigEndian)
+        );
+        assert_eq!(x.to_u8_bytes(), &*x.to_u8_bytes_in(ByteOrder::NATIVE));
+        assert_eq!(
+            Err(FromU16BytesError::OddLength { len: 5 }),
+            WString::from_utf16be_bytes(&b[1..])
+        );
+
+        let (x, order) =
+            WString::from_utf16_bytes_with_bom(&[0xFE, 0xFF, 0x00, 0x74])
+                .unwrap();
+        assert_eq!(
+            ("t".to_string(), ByteOrder::BigEndian),
+            (x.to_string_lossy(), order)
+        );
+        let (x, order) =
+            WString::from_utf16_bytes_with_bom(&[0xFF, 0xFE, 0x74, 0x00])
+                .unwrap();
+        assert_eq!(
+            ("t".to_string(), ByteOrder::LittleEndian),
+            (x.to_string_lossy(), order)
+        );
+        let (x, order) =
+            WString::from_utf16_bytes_with_bom(&[0x74, 0x00]).unwrap();
+        assert_eq!(
+            ("t".to_string(), ByteOrder::LittleEndian),
+            (x.to_string_lossy(), order)
+        );
+        let (x, _) = WString::from_utf16_bytes_with_bom(&[0xFE, 0xFF]).unwrap();
+        assert!(x.to_bytes().is_empty());
+
+        // The string ends at the first null character.
+        let x = WString::from_utf16be_bytes(&[0x00, 0x74, 0, 0, 0x00, 0x65])
+            .unwrap();
+        assert_eq!(&[0x74, 0x00], x.as_bytes_with_nul());
+        let (x, _) = WString::from_utf16_bytes_with_bom(&[
+            0xFE, 0xFF, 0x00, 0x74, 0, 0, 0x00, 0x65,
+        ])
+        .unwrap();
+        assert_eq!(&[0x74, 0x00], x.as_bytes_with_nul());
+    }
+
+    #[test]
+    fn test_into_raw() {
+        let x = WString::from_str("test🍣").unwrap();
+        let p = x.as_ptr();
+        let r = x.into_raw();
+        assert_eq!(p, r.cast_const());
+        let x = unsafe { WString::from_raw(r) };
+        assert_eq!("test🍣", x.to_string_lossy());
+        assert_eq!(p, x.as_ptr());
+
+        let x = AString::new(b"test".to_vec()).unwrap();
+        let r = x.into_raw();
+        let x = unsafe { AString::from_raw(r) };
+        assert_eq!(b"test\0", x.as_bytes_with_nul());
+
+        // An interior null character and an overwritten terminator written
+        // by safe code do not break the layout.
+        let mut x = WString::from_str("test").unwrap();
+        x.as_bytes_with_nul_mut()[1] = 0;
+        let x = unsafe { WString::from_raw(x.into_raw()) };
+        assert_eq!(&[0x74, 0], x.as_bytes_with_nul());
+        let mut x = WString::from_str("test").unwrap();
+        x.as_bytes_with_nul_mut()[4] = 0x21;
+        let x = unsafe { WString::from_raw(x.into_raw()) };
+        assert_eq!("test!", x.to_string_lossy());
+        let mut x = AString::new(b"test".to_vec()).unwrap();
+        unsafe { *x.as_mut_c_str().as_mut_u8_ptr().add(2) = 0 };
+        let x = unsafe { AString::from_raw(x.into_raw()) };
+        assert_eq!(b"te\0", x.as_bytes_with_nul());
+    }
+
+    #[test]
+    fn test_boxed_str() {
+        let x = WString::from_str("test").unwrap();
+        let p = x.as_ptr();
+        let b: Box<WStr> = x.into_boxed_wstr();
+        assert_eq!(p, b.as_ptr());
+        assert_eq!("test", b.to_string_lossy());
+        let x = WString::from(b);
+        assert_eq!(p, x.as_ptr());
+        assert_eq!("test", x.to_string_lossy());
+
+        let x = AString::new(b"test".to_vec()).unwrap();
+        let p = x.as_ptr();
+        let b = Box::<AStr>::from(x);
+        assert_eq!(p, b.as_ptr());
+        let x = b.into_astring();
+        assert_eq!(p, x.as_ptr());
+        assert_eq!(b"test\0", x.as_bytes_with_nul());
+    }
+
+    #[test]
+    fn test_ownership_traits() {
+        use std::{borrow::Cow, collections::HashMap, rc::Rc, sync::Arc};
+
+        let x = WString::from_str("test").unwrap();
+        let mut m = HashMap::new();
+        m.insert(x.clone(), 1);
+        assert_eq!(Some(&1), m.get(x.as_c_str()));
+        assert_eq!(None, m.get(wstr!("tes")));
+        let o: WString = wstr!("test").to_owned();
+        assert_eq!(x, o);
+
+        let c: Cow<'_, WStr> = Cow::from(wstr!("test"));
+        assert!(matches!(c, Cow::Borrowed(_)));
+        assert_eq!(x, c.into_owned());
+        let c = Cow::from(x.clone());
+        assert!(matches!(c, Cow::Owned(_)));
+        assert_eq!(x, WString::from(c));
+
+        let b = Box::<WStr>::from(x.as_c_str());
+        let b2 = b.clone();
+        assert_eq!(b, b2);
+        assert_ne!(b.as_ptr(), b2.as_ptr());
+        assert_eq!(&[0x74, 0x65, 0x73, 0x74, 0x00], b2.to_bytes_with_nul());
+        let r = Rc::<WStr>::from(x.as_c_str());
+        assert_eq!(&*r, x.as_c_str());
+        let a = Arc::<WStr>::from(x.clone());
+        assert_eq!(&*a, x.as_c_str());
+
+        assert_eq!(&[0x00], <&WStr>::default().to_bytes_with_nul());
+        assert_eq!(&[0x00], WString::default().as_bytes_with_nul());
+        assert_eq!(&[0x00], Box::<WStr>::default().to_bytes_with_nul());
+
+        let x = AString::new(b"test".to_vec()).unwrap();
+        let mut m = HashMap::new();
+        m.insert(x.clone(), 1);
+        assert_eq!(Some(&1), m.get(astr!("test")));
+        assert_eq!(x, astr!("test").to_owned());
+        let c = Cow::from(&x);
+        assert_eq!(x, c.into_owned());
+        let b = Box::<AStr>::from(astr!("test"));
+        assert_eq!(b.clone().to_bytes_with_nul(), b"test\0");
+        let r = Rc::<AStr>::from(x.clone());
+        let a = Arc::<AStr>::from(astr!("test"));
+        assert_eq!(&*r, &*a);
+        assert_eq!(b"\0", <&AStr>::default().to_bytes_with_nul());
+        assert_eq!(b"\0", AString::default().as_bytes_with_nul());
+    }
+
+    /// A mock allocator that counts freed strings.
+    struct MockDeallocator(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Deallocator for MockDeallocator {
+        unsafe fn deallocate(&mut self, ptr: *mut std::ffi::c_void) {
+            unsafe { drop(WString::from_raw(ptr as *mut u16)) };
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_foreign_wstring() {
+        let freed = std::rc::Rc::new(std::cell::Cell::new(0));
+        let ptr = WString::from_str("test🍣").unwrap().into_raw();
+        let s = unsafe {
+            ForeignWString::from_raw(ptr, MockDeallocator(freed.clone()))
+        };
+        assert_eq!("test🍣", s.to_string_lossy());
+        assert_eq!(&[0x74, 0x65, 0x73, 0x74, 0xD83C, 0xDF63], s.to_bytes());
+        assert_eq!(s.as_c_str(), &*WString::from_str("test🍣").unwrap());
+        assert_eq!(0, freed.get());
+        drop(s);
+        assert_eq!(1, freed.get());
+
+        let ptr = WString::from_str("test").unwrap().into_raw();
+        let s = unsafe {
+            ForeignWString::from_raw_s(ptr, 10, MockDeallocator(freed.clone()))
+        };
+        assert_eq!(&[0x74, 0x65, 0x73, 0x74, 0x00], s.to_bytes_with_nul());
+        let ptr = s.into_raw();
+        assert_eq!(1, freed.get());
+        let s = unsafe {
+            ForeignWString::from_raw(ptr, |p: *mut std::ffi::c_void| {
+                drop(WString::from_raw(p as *mut u16));
+                freed.set(freed.get() + 1);
+            })
+        };
+        assert_eq!("test", s.to_string_lossy());
+        drop(s);
+        assert_eq!(2, freed.get());
+    }
+
+    #[test]
+    fn test_search() {
+        let s = WString::from_str("C:/Windows/System32/🍣.dll").unwrap();
+        assert_eq!(Some(2), s.find("/"));
+        assert_eq!(Some(19), s.rfind('/'));
+        assert_eq!(Some(20), s.find('🍣'));
+        assert!(s.contains("System32"));
+        assert!(s.contains(&WString::from_str("🍣.").unwrap()));
+        assert!(!s.contains("system32"));
+        assert!(s.starts_with("C:"));
+        assert!(s.ends_with(".dll"));
+        assert_eq!(
+            "C:\\Windows\\System32\\🍣.dll",
+            s.replace('/', '\\').to_string_lossy()
+        );
+        assert_eq!(
+            "C:/🍣.dll",
+            s.replace("/Windows/System32", "").to_string_lossy()
+        );
+        assert_eq!(s, s.replace("", "x"));
+        let v: Vec<String> =
+            s.split("/").map(String::from_utf16_lossy).collect();
+        assert_eq!(["C:", "Windows", "System32", "🍣.dll"], v.as_slice());
+        let (a, b) = s.split_once('/').unwrap();
+        assert_eq!("C:", String::from_utf16_lossy(a));
+        assert_eq!("Windows/System32/🍣.dll", b.to_string_lossy());
+
+        let s = AString::new("KEY=VALUE=1\r\n\n x\n").unwrap();
+        assert_eq!(Some(3), s.find('='));
+        assert_eq!(Some(9), s.rfind("="));
+        assert!(s.contains(b'\n'));
+        assert!(!s.contains('ー'));
+        assert_eq!(None, s.find("ー"));
+        let (k, v) = s.split_once(&AString::new("=").unwrap()).unwrap();
+        assert_eq!(b"KEY", k);
+        assert_eq!(b"VALUE=1\r\n\n x\n", v.to_bytes());
+        assert_eq!(b"KEY-VALUE-1\r\n\n x\n", s.replace('=', "-").as_bytes());
+        let v: Vec<&[u8]> = s.lines().collect();
+        assert_eq!([&b"KEY=VALUE=1"[..], b"", b" x"], v.as_slice());
+
+        // Non-ASCII patterns never match.
+        assert!(!s.starts_with("ー") && !s.ends_with('ー'));
+        assert_eq!(None, s.split_once('ー'));
+        assert_eq!(1, s.split("ー").count());
+        assert_eq!(s, s.replace('ー', "-"));
+        assert_eq!(s, s.replace("", "x"));
+
+        // Patterns are matched byte by byte regardless of the code page.
+        let s = AStr::from_bytes_with_nul(b"\x83\x5C\0").unwrap();
+        assert_eq!(Some(1), s.find('\\'));
+        assert!(!s.is_char_boundary(1, CP_SHIFT_JIS));
+    }
+
+    #[test]
+    #[should_panic(expected = "replacement contains a non-ASCII character")]
+    fn test_astr_replace_non_ascii() { astr!("a-b").replace('-', 'é'); }
+
+    #[test]
+    #[should_panic(expected = "replacement contains a null character")]
+    fn test_astr_replace_nul() { astr!("a-b").replace('-', 0u8); }
+
+    #[test]
+    #[should_panic(expected = "replacement contains a null character")]
+    fn test_wstr_replace_nul() { wstr!("a-b").replace('-', "\0"); }
+
+    #[test]
+    fn test_wtf8() {
+        // "a🍣", lone low surrogate, "b", lone high surrogate.
+        let v = [0x61, 0xD83C, 0xDF63, 0xDC00, 0x62, 0xD800, 0x00];
+        let w = WStr::from_bytes_with_nul(&v).unwrap();
+        let s = w.to_wtf8();
+        assert_eq!(b"a\xF0\x9F\x8D\xA3\xED\xB0\x80b\xED\xA0\x80", s.as_bytes());
+        assert_eq!(None, s.as_str());
+        assert_eq!("a🍣\u{FFFD}b\u{FFFD}", s.to_string_lossy());
+        assert_eq!("\"a🍣\\u{DC00}b\\u{D800}\"", format!("{:?}", s));
+        assert_eq!(w, &*WString::from_wtf8(&s).unwrap());
+        let s2 = Wtf8Buf::from_bytes(s.clone().into_bytes()).unwrap();
+        assert_eq!(s, s2);
+        assert_eq!(s, s.clone().into_string().unwrap_err());
+
+        let s = Wtf8Buf::from("test🍣");
+        assert_eq!(Some("test🍣"), s.as_str());
+        assert_eq!("test🍣", s.clone().into_string().unwrap());
+        assert_eq!(
+            WString::from_str("test🍣").unwrap(),
+            s.to_wstring().unwrap()
+        );
+        assert!(Wtf8::from_str("t\0t").to_wstring().is_err());
+
+        // A high surrogate followed by a low surrogate is joined.
+        let mut s = Wtf8Buf::from_wide(&[0x61, 0xD83C]);
+        s.push_wtf8(&Wtf8Buf::from_wide(&[0xDF63, 0x62]));
+        assert_eq!("a🍣b".as_bytes(), s.as_bytes());
+
+        // Surrogate pairs must be encoded as a supplementary character.
+        let e = Wtf8::from_bytes(b"ab\xED\xA0\xBC\xED\xBE\xA3").unwrap_err();
+        assert_eq!(2, e.valid_up_to());
+        assert_eq!(
+            1,
+            Wtf8::from_bytes(b"a\xC0\x80").unwrap_err().valid_up_to()
+        );
+        assert_eq!(
+            0,
+            Wtf8::from_bytes(b"\xF4\x90\x80\x80")
+                .unwrap_err()
+                .valid_up_to()
+        );
+        assert_eq!(0, Wtf8::from_bytes(b"\xED\xA0").unwrap_err().valid_up_to());
+        assert!(Wtf8::from_bytes(b"\xED\xB0\x80\xED\xA0\x80").is_ok());
+    }
+
+    #[test]
+    fn test_os_str() {
+        use std::{
+            ffi::{OsStr, OsString},
+            path::{Path, PathBuf},
+        };
+        let s = WString::from_os_str("C:\\Windows\\🍣").unwrap();
+        assert_eq!("C:\\Windows\\🍣", s.to_string_lossy());
+        assert_eq!(OsStr::new("C:\\Windows\\🍣"), s.to_os_string());
+        assert_eq!(Path::new("C:\\Windows\\🍣"), s.to_path_buf());
+        assert_eq!(s, WString::try_from(Path::new("C:\\Windows\\🍣")).unwrap());
+        assert_eq!(s, WString::try_from(PathBuf::from(&*s)).unwrap());
+        assert_eq!(s, WString::try_from(OsString::from(&*s)).unwrap());
+        assert_eq!(
+            FromOsStrError::InteriorNul { position: 1 },
+            WString::from_os_str("t\0t").unwrap_err()
+        );
+
+        // Unpaired surrogates are preserved.
+        let v = [0x61, 0xD800, 0x00];
+        let w = WStr::from_bytes_with_nul(&v).unwrap();
+        let s = w.to_os_string();
+        assert_eq!(w, &*WString::from_os_str(&s).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            assert_eq!(b"a\xED\xA0\x80", s.as_bytes());
+            assert_eq!(
+                FromOsStrError::InvalidWtf8 { valid_up_to: 1 },
+                WString::from_os_str(OsStr::from_bytes(b"a\xFF")).unwrap_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_text() {
+        use windy::detect::{Encoding, decode_text, detect};
+        let utf16le = |s: &str| {
+            s.encode_utf16()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<u8>>()
+        };
+        let utf16be = |s: &str| {
+            s.encode_utf16()
+                .flat_map(|x| x.to_be_bytes())
+                .collect::<Vec<u8>>()
+        };
+        let check = |b: &[u8], s: &str, encoding: Encoding, bom: bool| {
+            let (x, enc) = decode_text(b);
+            assert_eq!(s, x.to_string_lossy());
+            assert_eq!((encoding, bom), (enc.encoding(), enc.has_bom()));
+            enc.confidence()
+        };
+
+        // BOM
+        let s = "[Section]\r\nキー=値🍣\r\n";
+        let mut b = vec![0xFF, 0xFE];
+        b.extend(utf16le(s));
+        assert_eq!(100, check(&b, s, Encoding::Utf16Le, true));
+        let mut b = vec![0xFE, 0xFF];
+        b.extend(utf16be(s));
+        assert_eq!(100, check(&b, s, Encoding::Utf16Be, true));
+        let mut b = vec![0xEF, 0xBB, 0xBF];
+        b.extend(s.as_bytes());
+        assert_eq!(100, check(&b, s, Encoding::Utf8, true));
+
+        // No BOM
+        assert!(check(&utf16le(s), s, Encoding::Utf16Le, false) >= 90);
+        assert!(check(&utf16be(s), s, Encoding::Utf16Be, false) >= 90);
+        assert!(
+            check(&utf16be("key=value"), "key=value", Encoding::Utf16Be, false)
+                >= 90
+        );
+        assert!(check(s.as_bytes(), s, Encoding::Utf8, false) >= 90);
+        assert_eq!(
+            100,
+            check(b"key=value", "key=value", Encoding::Utf8, false)
+        );
+        assert_eq!(100, check(b"", "", Encoding::Utf8, false));
+        // "Bush hid the facts" is not UTF-16.
+        let b = b"Bush hid the facts";
+        assert_eq!(100, check(b, "Bush hid the facts", Encoding::Utf8, false));
+        // Shift_JIS "日本語" looks like CJK characters in UTF-16BE.
+        assert_eq!(
+            Encoding::Ansi,
+            detect(b"\x93\xFA\x96\x7B\x8C\xEA").encoding()
+        );
+        // Unpaired surrogates are not UTF-16.
+        let enc = detect(&[0x61, 0x00, 0x00, 0xD8, 0xDC, 0x62]);
+        assert_eq!(Encoding::Ansi, enc.encoding());
+        assert!(enc.confidence() <= 50);
+        // Windows-1252 is used on non-Windows hosts.
+        #[cfg(not(windows))]
+        check(b"caf\xE9 \x80", "caf\u{E9} \u{20AC}", Encoding::Ansi, false);
+    }
+
+    #[test]
+    fn test_bstring() {
+        let s = BString::<RustBStrAllocator>::from_wide_in(
+            &[0x74, 0x00, 0xD83C, 0xDF63],
+            RustBStrAllocator,
+        );
+        assert_eq!(4, s.len());
+        assert_eq!(8, s.byte_len());
+        assert_eq!(
+            Some(&[0x74, 0x00, 0xD83C, 0xDF63, 0x00][..]),
+            s.as_slice_with_nul()
+        );
+        // The layout of BSTR.
+        unsafe {
+            let p = s.as_ptr() as *const u8;
+            assert_eq!(0, p.sub(4) as usize % 4);
+            assert_eq!(8, (p.sub(4) as *const u32).read());
+        }
+        assert_eq!("t\0🍣", s.to_string_lossy());
+        assert!(s.to_wstring().is_err());
+        assert!(s.as_wstr().is_err());
+        assert_eq!(s.clone(), s);
+
+        let ptr = s.into_raw();
+        let b = unsafe { BStr::from_raw(ptr) };
+        assert_eq!(4, b.len());
+        let s = unsafe { BString::from_raw_in(ptr, RustBStrAllocator) };
+        drop(s);
+
+        let w = WString::from_str("test").unwrap();
+        let s = BString::<RustBStrAllocator>::from_wide_in(
+            w.as_bytes(),
+            RustBStrAllocator,
+        );
+        assert_eq!(&*w, s.as_wstr().unwrap());
+        assert_eq!(w, s.to_wstring().unwrap());
+        assert!(*s == *w);
+        assert_eq!("\"test\"", format!("{:?}", s));
+
+        // A null BSTR is an empty string.
+        let b = unsafe { BStr::from_raw(std::ptr::null()) };
+        assert!(b.is_empty());
+        assert_eq!(0, b.byte_len());
+        assert_eq!(Some(&[0][..]), b.as_slice_with_nul());
+        let s = BString::<RustBStrAllocator>::default();
+        assert!(s.into_raw().is_null());
+        assert_eq!(b, <&BStr>::default());
+
+        // A BSTR of 3 bytes allocated by `SysAllocStringByteLen`.
+        let mut buf = [0u32; 3];
+        let bytes = [3, 0, 0, 0, b'a', b'b', b'c', 0, 0];
+        unsafe {
+            let p = buf.as_mut_ptr() as *mut u8;
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), p, bytes.len());
+        }
+        let b = unsafe { BStr::from_raw((buf.as_ptr() as *const u16).add(2)) };
+        assert_eq!(3, b.byte_len());
+        assert_eq!(1, b.len());
+        assert_eq!(b"abc", b.as_bytes());
+        assert_eq!(None, b.as_slice_with_nul());
+        assert!(b.as_wstr().is_err());
+        assert!(*b != *wstr!("a"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_default_bstring() {
+        let s = BString::from("te\0st");
+        assert_eq!(s, BString::from(&*s));
+        let ptr = s.into_raw();
+        let s = unsafe { BString::from_raw(ptr) };
+        assert_eq!("te\0st", s.to_string_lossy());
+        assert_eq!(
+            BString::from(wstr!("test")),
+            BString::from(&WString::from_str("test").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_hstring() {
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(24, std::mem::size_of::<HStringHeader>());
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(20, std::mem::size_of::<HStringHeader>());
+
+        let s = HString::from("test🍣");
+        assert_eq!("test🍣", s.to_string_lossy());
+        assert_eq!(Some(1), s.ref_count());
+        // The layout of the header.
+        unsafe {
+            let p = s.as_raw() as *const u8;
+            assert_eq!(0, (p as *const u32).read()); // flags
+            assert_eq!(6, (p.add(4) as *const u32).read()); // length
+            let data = (p.add(16) as *const *const u16).read();
+            assert_eq!(s.as_ptr(), data);
+            assert_eq!(0, data.add(6).read()); // null terminator
+            // The reference count follows the header.
+            let count =
+                p.add(std::mem::size_of::<HStringHeader>()) as *const u32;
+            assert_eq!(1, count.read());
+            let t = s.clone();
+            assert_eq!(2, count.read());
+            assert_eq!(s.as_ptr(), t.as_ptr());
+            drop(t);
+            assert_eq!(1, count.read());
+        }
+        let raw = s.into_raw();
+        let s = unsafe { HString::from_raw(raw) };
+        assert_eq!(s, HString::from(WString::from_str("test🍣").unwrap()));
+        assert_eq!(s, HString::from(&*WString::from_str("test🍣").unwrap()));
+        assert_eq!("\"test🍣\"", format!("{:?}", s));
+
+        // An empty string is a null HSTRING.
+        let e = HString::from("");
+        assert!(e.as_raw().is_null());
+        assert!(e.to_bytes().is_empty());
+        assert_eq!(None, e.ref_count());
+        assert_eq!(&[0], e.to_bytes_with_nul());
+        assert!(HString::from("\0test").as_raw().is_null());
+        assert_eq!("a", HString::from("a\0b").to_string_lossy());
+        let e = HString::from(wstr!(""));
+        assert!(e.as_raw().is_null());
+        assert_eq!(None, e.ref_count());
+        let e = HString::from(&WString::default());
+        assert!(e.as_raw().is_null());
+        assert_eq!(None, e.ref_count());
+
+        // Fast-pass HSTRING
+        let w = WString::from_str("fast").unwrap();
+        let r = HStringReference::new(&w);
+        assert!(r.header().is_reference());
+        unsafe {
+            let p = r.as_raw() as *const u8;
+            assert_eq!(1, (p as *const u32).read());
+            assert_eq!(4, (p.add(4) as *const u32).read());
+            assert_eq!(w.as_ptr(), (p.add(16) as *const *const u16).read());
+        }
+        // A fast-pass HSTRING is borrowed, and duplicated when it is cloned.
+        let s = std::mem::ManuallyDrop::new(unsafe {
+            HString::from_raw(r.as_raw())
+        });
+        assert_eq!(None, s.ref_count());
+        assert_eq!(w.as_ptr(), s.as_ptr());
+        let t = (*s).clone();
+        assert_eq!(Some(1), t.ref_count());
+        assert_ne!(w.as_ptr(), t.as_ptr());
+        assert_eq!(t, HString::from(&r));
+        assert!(HStringReference::new(wstr!("")).as_raw().is_null());
+    }
+
+    #[test]
+    fn test_multi_string() {
+        let s = WMultiString::from_strs(["C:\\", "D:\\"]).unwrap();
+        let v: Vec<String> = s.iter().map(|x| x.to_string_lossy()).collect();
+        assert_eq!(["C:\\", "D:\\"], v.as_slice());
+        assert_eq!(
+            "C:\\\0D:\\\0\0".encode_utf16().collect::<Vec<u16>>(),
+            s.as_bytes_with_nul()
+        );
+        assert_eq!(s, WMultiString::from_wstrs(s.iter()).unwrap());
+        assert_eq!(
+            Err(MultiStrError::EmptyString { index: 1 }),
+            WMultiString::from_strs(["a", ""])
+        );
+        assert_eq!(
+            Err(MultiStrError::InteriorNul { index: 0 }),
+            WMultiString::from_strs(["a\0b"])
+        );
+        let e = WMultiString::new();
+        assert!(e.is_empty());
+        assert_eq!([0], e.as_bytes_with_nul());
+        assert_eq!("[]", format!("{:?}", e));
+        assert_eq!(
+            "[\"a\", \"bc\"]",
+            format!("{:?}", WMultiString::from_strs(["a", "bc"]).unwrap())
+        );
+
+        // Malformed lists are repaired.
+        let l = WMultiString::from_bytes_lossy;
+        let ab = WMultiString::from_strs(["a", "b"]).unwrap();
+        assert_eq!(ab, l(&[0x61, 0, 0x62, 0, 0]));
+        assert_eq!(ab, l(&[0x61, 0, 0x62, 0]));
+        assert_eq!(ab, l(&[0x61, 0, 0x62]));
+        assert_eq!(ab, l(&[0x61, 0, 0x62, 0, 0, 0x63, 0, 0]));
+        assert_eq!(e, l(&[]));
+        assert_eq!(e, l(&[0]));
+        assert_eq!(e, l(&[0, 0]));
+
+        let mut a =
+            AMultiString::from_astrs([astr!("x"), astr!("yz")]).unwrap();
+        a.push(astr!("w")).unwrap();
+        assert_eq!(b"x\0yz\0w\0\0", a.as_bytes_with_nul());
+        assert_eq!(
+            Err(MultiStrError::EmptyString { index: 3 }),
+            a.push(astr!(""))
+        );
+        let nul = unsafe { AStr::from_bytes_with_nul_unchecked(b"a\0b\0") };
+        assert_eq!(Err(MultiStrError::InteriorNul { index: 3 }), a.push(nul));
+        assert_eq!(b"x\0yz\0w\0\0", a.as_bytes_with_nul());
+        let b: &AMultiStr = &a;
+        assert_eq!(a, b.to_owned());
+        assert_eq!(3, b.into_iter().count());
+    }
+
+    #[test]
+    fn test_env_block() {
+        let w = |s: &str| s.encode_utf16().collect::<Vec<u16>>();
+        let v = w("b=1\0=C:=C:\\x\0junk\0Path=C:\\Windows\0A=x=y\0\0");
+        let mut env =
+            EnvBlock::parse(WMultiStr::from_bytes_with_nul(&v).unwrap());
+        assert_eq!(4, env.len());
+        let names: Vec<String> =
+            env.iter().map(|(k, _)| k.to_string_lossy()).collect();
+        assert_eq!(["b", "=C:", "Path", "A"], names.as_slice());
+        assert_eq!(Some(wstr!("C:\\Windows")), env.get(wstr!("PATH")));
+        assert_eq!(Some(wstr!("C:\\Windows")), env.get(wstr!("path")));
+        assert_eq!(Some(wstr!("x=y")), env.get(wstr!("a")));
+        assert_eq!(Some(wstr!("C:\\x")), env.drive_dir('c'));
+        assert_eq!(None, env.drive_dir('D'));
+        assert_eq!(None, env.get(wstr!("Pat")));
+        // Non-ASCII names are compared case-insensitively.
+        env.set(wstr!("ÄÖ"), wstr!("1")).unwrap();
+        assert_eq!(Some(wstr!("1")), env.get(wstr!("äö")));
+
+        // The original name is kept.
+        env.set(wstr!("PATH"), wstr!("D:\\")).unwrap();
+        assert_eq!(5, env.len());
+        assert_eq!(
+            Some((wstr!("Path"), wstr!("D:\\"))),
+            env.iter().find(|(_, v)| *v == wstr!("D:\\"))
+        );
+        assert_eq!(Err(InvalidEnvNameError), env.set(wstr!(""), wstr!("x")));
+        assert_eq!(Err(InvalidEnvNameError), env.set(wstr!("a=b"), wstr!("x")));
+        assert_eq!(Some(wstr!("1").to_wstring()), env.remove(wstr!("äÖ")));
+        assert_eq!(None, env.remove(wstr!("äÖ")));
+
+        // Names are sorted case-insensitively, and `=C:` comes first.
+        env.set(wstr!("_X"), wstr!("")).unwrap();
+        assert_eq!(
+            w("=C:=C:\\x\0A=x=y\0b=1\0Path=D:\\\0_X=\0\0"),
+            env.to_block()
+        );
+        let parsed = EnvBlock::parse(&env.to_multi_string());
+        env.sort();
+        assert_eq!(env, parsed);
+
+        assert_eq!([0, 0], EnvBlock::new().to_block().as_slice());
+        assert!(EnvBlock::from(<&WMultiStr>::default()).is_empty());
+        let env: EnvBlock = vec![
+            (wstr!("x").to_wstring(), wstr!("1").to_wstring()),
+            (wstr!("X").to_wstring(), wstr!("2").to_wstring()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(1, env.len());
+        assert_eq!(Some(wstr!("2")), env.get(wstr!("x")));
+        assert!(!EnvBlock::from_env().is_empty());
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let mut env = EnvBlock::new();
+        env.set(wstr!("SystemRoot"), wstr!("C:\\Windows")).unwrap();
+        env.set(wstr!("A"), wstr!("%B%")).unwrap();
+        env.set(wstr!("B"), wstr!("b")).unwrap();
+        env.set(wstr!("=C:"), wstr!("C:\\x")).unwrap();
+        env.set(wstr!("EMPTY"), wstr!("")).unwrap();
+        let t = |src: &str, expected: &str| {
+            let s = WString::from_str(src).unwrap().expand_env_in(&env);
+            assert_eq!(expected, s.unwrap().to_string_lossy(), "{:?}", src);
+        };
+        t("", "");
+        t("abc", "abc");
+        t("%SystemRoot%", "C:\\Windows");
+        t("%systemroot%\\%SYSTEMROOT%", "C:\\Windows\\C:\\Windows");
+        // No recursion.
+        t("%A%", "%B%");
+        t("%B%%B%", "bb");
+        // Unmatched references are left intact.
+        t("%X%", "%X%");
+        t("%X%B%", "%X%B%");
+        t("%X%%B%", "%X%b");
+        t("%", "%");
+        t("%%", "%%");
+        t("%%B%", "%%B%");
+        t("100%", "100%");
+        t("%B", "%B");
+        t("a%B%c%", "abc%");
+        t("%B %", "%B %");
+        t("%=C:%", "C:\\x");
+        t("[%EMPTY%]", "[]");
+
+        // A lookup is called once per reference with the raw name.
+        let names = std::cell::RefCell::new(Vec::new());
+        let s = wstr!("%a%%Bc%%%d")
+            .expand_env_with(&|name| {
+                names.borrow_mut().push(name.to_string_lossy());
+                None
+            })
+            .unwrap();
+        assert_eq!(wstr!("%a%%Bc%%%d"), s.as_ref());
+        assert_eq!(["a", "Bc"], names.borrow().as_slice());
+
+        // Length limits.
+        let max = UNICODE_STRING_MAX_CHARS;
+        let long = WString::new(vec![b'a' as u16; max - 1]).unwrap();
+        assert_eq!(Ok(long.clone()), long.expand_env_in(&env));
+        let longer = WString::new(vec![b'a' as u16; max]).unwrap();
+        assert_eq!(
+            Err(ExpandEnvError::SourceTooLong),
+            longer.expand_env_in(&env)
+        );
+        let mut v = vec![b'a' as u16; max - 4];
+        v.extend("%B%".encode_utf16());
+        let s = WString::new(v).unwrap();
+        assert_eq!(max - 3, s.expand_env_in(&env).unwrap().to_bytes().len());
+        let f = |x: &'static WStr| move |_: &WStr| Some(x.to_wstring());
+        let t = s.expand_env_with(&f(wstr!("bbb"))).unwrap();
+        assert_eq!(max - 1, t.to_bytes().len());
+        let s = s.expand_env_with(&f(wstr!("bbbb")));
+        assert_eq!(Err(ExpandEnvError::ResultTooLong { required: max + 1 }), s);
+    }
+
+    #[test]
+    fn test_truncate_to_fit() {
+        let mut s = AString::new(b"\x83\x41\x83\x43".to_vec()).unwrap();
+        s.truncate_to_fit(4, CP_SHIFT_JIS);
+        assert_eq!(b"\x83\x41\x83\x43", s.as_bytes());
+        s.truncate_to_fit(3, CP_SHIFT_JIS);
+        assert_eq!(b"\x83\x41\0", s.as_bytes_with_nul());
+        s.truncate_to_fit(1, CP_SHIFT_JIS);
+        assert_eq!(b"\0", s.as_bytes_with_nul());
+        let mut s = AString::new(b"\x83\x41\x83\x43".to_vec()).unwrap();
+        s.truncate_to_fit(3, 1252);
+        assert_eq!(b"\x83\x41\x83", s.as_bytes());
+    }
+
+    #[test]
+    #[cfg(windows)]
     fn test_string_conversion() {
         // UTF-8 -> ANSI
         let _ = AString::from_str_lossy("スペシャル").to_string_lossy();
@@ -144,6 +962,7 @@ pub mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_string_concatenation() {
         let x = AString::from_str("hello ").unwrap();
         let y = AString::from_str("world").unwrap();
@@ -158,6 +977,7 @@ pub mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_string_conversion_invalid() {
         // UTF-8 -> ANSI (Invalid)
         assert_eq!(
@@ -177,6 +997,7 @@ pub mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_empty_string() {
         assert_eq!(
             "",
@@ -263,6 +1084,49 @@ pub mod tests2 {
         }
     }
 
+    #[test]
+    fn test_wstr_from_bytes_with_nul() {
+        let x = &[0x0074, 0x0065, 0x0073, 0x0074, 0x0000];
+        let s = WStr::from_bytes_with_nul(x).unwrap();
+        assert_eq!(x, s.to_bytes_with_nul());
+        assert_eq!(
+            Err(FromBytesWithNulError::InteriorNul { position: 2 }),
+            WStr::from_bytes_with_nul(&[0x0074, 0x0065, 0x0000, 0x0000])
+        );
+        assert_eq!(
+            Err(FromBytesWithNulError::NotNulTerminated { position: 2 }),
+            WStr::from_bytes_with_nul(&[0x0074, 0x0065])
+        );
+        assert_eq!(
+            Err(FromBytesWithNulError::NotNulTerminated { position: 0 }),
+            WStr::from_bytes_with_nul(&[])
+        );
+        let s = WStr::from_bytes_until_nul(&[0x0074, 0x0000, 0x0065]).unwrap();
+        assert_eq!(&[0x0074, 0x0000], s.to_bytes_with_nul());
+        let s = WStr::from_bytes_until_nul(x).unwrap();
+        assert_eq!(x, s.to_bytes_with_nul());
+        let e = WStr::from_bytes_until_nul(&[0x0074, 0x0065]).unwrap_err();
+        assert_eq!(2, e.nul_position());
+    }
+
+    #[test]
+    fn test_astr_from_bytes_with_nul() {
+        let x = b"test\0";
+        let s = AStr::from_bytes_with_nul(x).unwrap();
+        assert_eq!(x, s.to_bytes_with_nul());
+        let e = AStr::from_bytes_with_nul(b"\0test\0").unwrap_err();
+        assert_eq!(FromBytesWithNulError::InteriorNul { position: 0 }, e);
+        assert_eq!(0, e.nul_position());
+        assert_eq!(
+            Err(FromBytesWithNulError::NotNulTerminated { position: 4 }),
+            AStr::from_bytes_with_nul(b"test")
+        );
+        let s = AStr::from_bytes_until_nul(b"te\0st\0").unwrap();
+        assert_eq!(b"te\0", s.to_bytes_with_nul());
+        let e = AStr::from_bytes_until_nul(b"test").unwrap_err();
+        assert_eq!(4, e.nul_position());
+    }
+
     #[test]
     fn test_ansi_string() {
         let x = &mut [0x74, 0x65, 0x73, 0x74, 0x00];
@@ -290,4 +1154,228 @@ pub mod tests2 {
             assert_eq!(10, a.as_raw().MaximumLength);
         }
     }
+
+    static CONST_UNICODE_STRING: UnicodeString<'static> =
+        UnicodeString::from_static(wstr!("test🍣"));
+
+    static CONST_ANSI_STRING: AnsiString<'static> =
+        AnsiString::from_static(astr!("test"));
+
+    #[test]
+    fn test_const_unicode_string() {
+        let s = wstr!("test🍣");
+        assert_eq!(
+            &[0x0074, 0x0065, 0x0073, 0x0074, 0xD83C, 0xDF63, 0x0000],
+            s.to_bytes_with_nul()
+        );
+        assert_eq!(&[0x0000], wstr!("").to_bytes_with_nul());
+        let a = &CONST_UNICODE_STRING;
+        assert_eq!(s, &**a);
+        assert_eq!(a.to_bytes_with_nul().as_ptr(), a.as_raw().Buffer);
+        assert_eq!(12, a.as_raw().Length);
+        assert_eq!(14, a.as_raw().MaximumLength);
+        let b = UnicodeString::new(a);
+        assert_eq!(b.as_raw().Length, a.as_raw().Length);
+        assert_eq!(b.as_raw().MaximumLength, a.as_raw().MaximumLength);
+
+        // A long string is truncated like `RtlInitUnicodeString`.
+        let mut v = vec![0x61; 0x8001];
+        v[0x8000] = 0;
+        let b = UnicodeString::new(WStr::from_bytes_with_nul(&v).unwrap());
+        assert_eq!(0xFFFC, b.as_raw().Length);
+        assert_eq!(0xFFFE, b.as_raw().MaximumLength);
+        let mut v = vec![0x61; 0x10001];
+        v[0x10000] = 0;
+        let b = AnsiString::new(AStr::from_bytes_with_nul(&v).unwrap());
+        assert_eq!(0xFFFE, b.as_raw().Length);
+        assert_eq!(0xFFFF, b.as_raw().MaximumLength);
+    }
+
+    #[test]
+    fn test_str_macros() {
+        const W: &WStr = wstr!("スペシャル");
+        assert_eq!(
+            &[0x30B9, 0x30DA, 0x30B7, 0x30E3, 0x30EB, 0x0000],
+            W.to_bytes_with_nul()
+        );
+        const A: &AStr = astr!("test");
+        assert_eq!(b"test\0", A.to_bytes_with_nul());
+        assert_eq!(b"\0", astr!("").to_bytes_with_nul());
+        assert_eq!(4, A.to_bytes().len());
+    }
+
+    #[test]
+    fn test_trim_and_split() {
+        let s = wstr!(" \t a b\u{3000}\r\n");
+        assert_eq!(&[0x61, 0x20, 0x62], s.trim());
+        assert_eq!(
+            &[0x61, 0x20, 0x62, 0x3000, 0x0D, 0x0A],
+            s.trim_start().to_bytes()
+        );
+        assert_eq!(&[0x20, 0x09, 0x20, 0x61, 0x20, 0x62], s.trim_end());
+        assert_eq!(&[] as &[u16], wstr!(" \r\n").trim());
+        assert_eq!(&[0], wstr!(" ").trim_start().to_bytes_with_nul());
+        let mut it = s.split(0x20u16);
+        assert_eq!(Some(&[][..]), it.next());
+        assert_eq!(Some(&[0x09][..]), it.next());
+        assert_eq!(Some(&[0x61][..]), it.next());
+        assert_eq!(Some(&[0x62, 0x3000, 0x0D, 0x0A][..]), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(1, wstr!("").split(';').count());
+        assert_eq!(0, wstr!("").lines().count());
+        let mut it = wstr!("a\r\nb\n\nc").lines();
+        assert_eq!(Some(&[0x61][..]), it.next());
+        assert_eq!(Some(&[0x62][..]), it.next());
+        assert_eq!(Some(&[][..]), it.next());
+        assert_eq!(Some(&[0x63][..]), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(None, wstr!("abc").split_once(wstr!("bd")));
+
+        let s = astr!("  a b \r\n");
+        assert_eq!(b"a b", s.trim());
+        assert_eq!(b"a b \r\n", s.trim_start().to_bytes());
+        assert_eq!(b"  a b", s.trim_end());
+        let mut it = astr!("a;b;;").split(&b";"[..]);
+        assert_eq!(Some(&b"a"[..]), it.next());
+        assert_eq!(Some(&b"b"[..]), it.next());
+        assert_eq!(Some(&b""[..]), it.next());
+        assert_eq!(Some(&b""[..]), it.next());
+        assert_eq!(None, it.next());
+        assert!(astr!("test").starts_with(astr!("te")));
+        assert!(astr!("test").ends_with('t'));
+    }
+
+    #[test]
+    fn test_chars() {
+        // "a🍣", lone low surrogate, "b", lone high surrogate.
+        let v = [0x61, 0xD83C, 0xDF63, 0xDC00, 0x62, 0xD800, 0x00];
+        let s = WStr::from_bytes_with_nul(&v).unwrap();
+        let mut it = s.chars();
+        assert_eq!(Some(Ok('a')), it.next());
+        assert_eq!(Some(Ok('🍣')), it.next());
+        let e = it.next().unwrap().unwrap_err();
+        assert_eq!((3, 0xDC00), (e.position(), e.unit()));
+        assert_eq!(Some(Ok('b')), it.next());
+        let e = it.next().unwrap().unwrap_err();
+        assert_eq!((5, 0xD800), (e.position(), e.unit()));
+        assert_eq!(None, it.next());
+
+        let mut it = s.char_indices().rev();
+        assert_eq!(5, it.next().unwrap().1.unwrap_err().position());
+        assert_eq!(Some((4, Ok('b'))), it.next());
+        assert_eq!(3, it.next().unwrap().1.unwrap_err().position());
+        assert_eq!(Some((1, Ok('🍣'))), it.next());
+        assert_eq!(Some((0, Ok('a'))), it.next());
+        assert_eq!(None, it.next());
+
+        let mut it = s.chars_lossy();
+        assert_eq!(Some('a'), it.next());
+        assert_eq!(Some('🍣'), it.next());
+        assert_eq!(Some('\u{FFFD}'), it.next());
+        assert_eq!(Some('b'), it.next());
+        assert_eq!(Some('\u{FFFD}'), it.next());
+        assert_eq!(None, it.next());
+
+        let mut it = s.code_points();
+        assert_eq!(Some(0x61), it.next());
+        assert_eq!(Some(0xD800), it.next_back());
+        assert_eq!(Some(0x1F363), it.next());
+        assert_eq!(Some(0xDC00), it.next());
+        assert_eq!(Some(0x62), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(0, wstr!("").chars().count());
+    }
+
+    #[test]
+    fn test_chars_cp() {
+        // "ソ" (0x83 0x5C), "\\", "ア" (0x83 0x41), "a" in Shift_JIS.
+        let s = AStr::from_bytes_with_nul(b"\x83\x5C\x5C\x83\x41a\0").unwrap();
+        let mut it = s.chars_cp(CP_SHIFT_JIS);
+        assert_eq!(Some(&b"\x83\x5C"[..]), it.next());
+        assert_eq!(Some(&b"\x5C"[..]), it.next());
+        assert_eq!(Some(&b"\x83\x41"[..]), it.next());
+        assert_eq!(Some(&b"a"[..]), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(6, s.chars_cp(1252).count());
+        let boundaries = [true, false, true, true, false, true, true, false];
+        for (i, &b) in boundaries.iter().enumerate() {
+            assert_eq!(b, s.is_char_boundary(i, CP_SHIFT_JIS), "{}", i);
+        }
+        assert_eq!(0, s.floor_char_boundary(1, CP_SHIFT_JIS));
+        assert_eq!(3, s.floor_char_boundary(4, CP_SHIFT_JIS));
+        assert_eq!(6, s.floor_char_boundary(100, CP_SHIFT_JIS));
+        assert_eq!(4, s.floor_char_boundary(4, 1252));
+
+        // A lead byte at the end is treated as a single-byte character.
+        let s = AStr::from_bytes_with_nul(b"a\x81\0").unwrap();
+        assert_eq!(2, s.chars_cp(CP_GBK).count());
+        // GB 18030 four-byte sequence.
+        let s = AStr::from_bytes_with_nul(b"\x81\x30\x81\x30a\0").unwrap();
+        assert_eq!(2, s.chars_cp(CP_GB18030).count());
+        assert_eq!(3, s.chars_cp(CP_GBK).count());
+        // UTF-8 and an invalid sequence.
+        let s =
+            AStr::from_bytes_until_nul(b"\xE3\x82\xA2\xF0\x9F\x8D\xA3\xE3a\0")
+                .unwrap();
+        let v: Vec<usize> = s.chars_cp(CP_UTF8).map(|x| x.len()).collect();
+        assert_eq!([3, 4, 1, 1], v.as_slice());
+    }
+
+    #[test]
+    fn test_const_ansi_string() {
+        let a = &CONST_ANSI_STRING;
+        assert_eq!(b"test\0", a.to_bytes_with_nul());
+        assert_eq!(4, a.as_raw().Length);
+        assert_eq!(5, a.as_raw().MaximumLength);
+        let b = AnsiString::new(a);
+        assert_eq!(b.as_raw().Length, a.as_raw().Length);
+        assert_eq!(b.as_raw().MaximumLength, a.as_raw().MaximumLength);
+    }
+
+    #[test]
+    fn test_multi_str() {
+        let s = WMultiStr::from_bytes_with_nul(&[0x61, 0, 0x62, 0x63, 0, 0])
+            .unwrap();
+        let mut it = s.iter();
+        assert_eq!(wstr!("a"), it.next().unwrap());
+        assert_eq!(wstr!("bc"), it.next().unwrap());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next());
+        assert!(!s.is_empty());
+        assert_eq!(s, unsafe { WMultiStr::from_raw(s.as_ptr()) });
+
+        let e = WMultiStr::from_bytes_with_nul(&[0]).unwrap();
+        assert!(e.is_empty());
+        assert_eq!(0, e.iter().count());
+        assert_eq!(e, WMultiStr::from_bytes_with_nul(&[]).unwrap());
+        assert_eq!(e, unsafe { WMultiStr::from_raw(e.as_ptr()) });
+        assert_eq!(
+            Err(MultiStrError::NotDoubleNulTerminated),
+            WMultiStr::from_bytes_with_nul(&[0x61, 0])
+        );
+        assert_eq!(
+            Err(MultiStrError::NotDoubleNulTerminated),
+            WMultiStr::from_bytes_with_nul(&[0x61])
+        );
+        assert_eq!(
+            Err(MultiStrError::InteriorDoubleNul { position: 2 }),
+            WMultiStr::from_bytes_with_nul(&[0x61, 0, 0, 0x62, 0, 0])
+        );
+        assert_eq!(e, WMultiStr::from_bytes_with_nul(&[0, 0]).unwrap());
+        assert_eq!(
+            Err(MultiStrError::InteriorDoubleNul { position: 0 }),
+            WMultiStr::from_bytes_with_nul(&[0, 0, 0])
+        );
+
+        // Trailing data such as padding is ignored.
+        let s =
+            AMultiStr::from_bytes_until_double_nul(b"a\0b\0\0\0\0c").unwrap();
+        assert_eq!(b"a\0b\0\0", s.as_bytes_with_nul());
+        let v: Vec<&[u8]> = s.iter().map(|x| x.to_bytes()).collect();
+        assert_eq!([b"a", b"b"], v.as_slice());
+        assert_eq!(
+            Err(MultiStrError::NotDoubleNulTerminated),
+            AMultiStr::from_bytes_until_double_nul(b"a\0b")
+        );
+    }
 }